@@ -0,0 +1,854 @@
+// Command line definition for the crunch binary.
+//
+// Running `crunch <input> <output>` without a subcommand is the same as
+// `crunch evaluate <input> <output>`.
+//
+// Some options fall back to a CRUNCH_* environment variable, and then to
+// the config file, when they are not given.
+
+use clap::{ Args, Parser, Subcommand, ValueEnum };
+use anyhow::Result;
+use crunch::input::Discover;
+use crunch::output::{ Options, RepoLink };
+use crunch::{ AssertType, EventFilter, Filter, GroupBy, Report, SortKey };
+use glob::Pattern;
+use regex::Regex;
+use serde::{ Deserialize };
+use std::env;
+use std::io::{ self, BufRead, IsTerminal };
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Evaluate the assertions found in Antithesis SDK output")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Config file with the defaults for options not given here
+    /// (default: the nearest crunch.toml in this or a parent directory)
+    #[arg(long, global = true, value_name = "FILE", env = "CRUNCH_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub evaluate: EvaluateArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Evaluate every assertion and write one JSON line per assertion
+    Evaluate(Box<EvaluateArgs>),
+    /// Print a table of the assertions and their totals
+    Summary(SummaryArgs),
+    /// Check that every line of the input can be parsed, and look for
+    /// assertions that were never registered, never hit or registered twice
+    Validate(ValidateArgs),
+    /// Export the assertions, their hits and the events into a database
+    Export(ExportArgs),
+    /// Print the JSON Schema of a report or of the SDK input
+    Schema(SchemaArgs),
+    /// Extract the user events, without evaluating the assertions
+    Events(EventsArgs),
+    /// Compare the assertions of two runs, given as `json` or `jsonl`
+    /// reports or as SDK output
+    Diff(DiffArgs),
+    /// Combine the `json` or `jsonl` reports of several runs into one,
+    /// deciding again whether each assertion passed
+    Merge(MergeArgs),
+    /// Compare the assertions over several runs and flag those that both
+    /// passed and failed
+    Flaky(FlakyArgs),
+    /// Show the result of one assertion in each of the last runs recorded
+    /// with `evaluate --history`
+    History(HistoryArgs),
+    /// Follow SDK output as it is written and keep rewriting the report
+    /// of what was read so far
+    Tail(TailArgs),
+    /// Watch directories of SDK output, reread the files that are added or
+    /// changed and keep rewriting the merged report
+    Watch(WatchArgs),
+    /// Take SDK output over TCP, one JSON message per line, and keep
+    /// rewriting the report of everything received
+    Listen(ListenArgs),
+    /// Run an HTTP server with a dashboard of a report, or of the SDK output
+    /// posted to it with --ingest
+    Serve(ServeArgs),
+    /// Browse the assertions of a report, or of SDK output, in the terminal
+    Tui(TuiArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct EvaluateArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    #[command(flatten)]
+    pub sort: SortArgs,
+
+    #[command(flatten)]
+    pub events: EventFilterArgs,
+
+    /// File to write the evaluated assertions to, `-` for stdout (the default)
+    #[arg(short, long, env = "CRUNCH_OUTPUT")]
+    pub output: Option<String>,
+
+    /// How to write the evaluated assertions [default: jsonl]
+    #[arg(short, long, value_enum, env = "CRUNCH_FORMAT")]
+    pub format: Option<Format>,
+
+    /// Render the report through a Tera template instead of using --format
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Extra csv/tsv columns taken from the details (e.g. `request.method`)
+    #[arg(long, value_name = "KEY", value_delimiter = ',')]
+    pub detail_columns: Vec<String>,
+
+    /// Nest the markdown assertions under their file, class, function or display-type
+    #[arg(long)]
+    pub group_by: Option<GroupBy>,
+
+    /// List the N failed assertions with the most counterexample hits first (markdown)
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// Directory the assertion locations are relative to: show the source
+    /// lines around each failure (markdown)
+    #[arg(long, value_name = "DIR")]
+    pub source_root: Option<PathBuf>,
+
+    /// Link each location to its line in this repository, e.g.
+    /// https://github.com/org/repo (markdown, and `permalink` in templates)
+    #[arg(long, value_name = "URL")]
+    pub repo_url: Option<String>,
+
+    /// Revision to link to with --repo-url (default: the commit checked out)
+    #[arg(long, value_name = "SHA", requires = "repo_url")]
+    pub rev: Option<String>,
+
+    /// TOML file of assertion `ids` and `regexes` whose failures are expected
+    /// and don't affect the exit code
+    #[arg(long, value_name = "FILE")]
+    pub xfail: Option<PathBuf>,
+
+    /// TOML file mapping display types to error, warning or info,
+    /// only failed errors affect the exit code
+    #[arg(long, value_name = "FILE")]
+    pub severity: Option<PathBuf>,
+
+    /// Also list every user event in a `timeline`, ordered by their
+    /// `timestamp`, `ts` or `time` field when they all have one and by
+    /// input order otherwise
+    #[arg(long)]
+    pub events_timeline: bool,
+
+    /// Attach the last N user events read before the hit that failed each
+    /// failed assertion, as its `context_events`
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub context_events: usize,
+
+    /// Also list up to N distinct example and counterexample details per
+    /// assertion, rather than only the last of each
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub keep_examples: usize,
+
+    /// Replace the values of these keys, anywhere in the details, with "<redacted>"
+    #[arg(long, value_name = "KEY", value_delimiter = ',')]
+    pub redact: Vec<String>,
+
+    /// Replace example and counterexample details larger than this, as JSON,
+    /// with `{"truncated": true, ...}` and a preview of their start
+    #[arg(long, value_name = "BYTES")]
+    pub max_details_bytes: Option<usize>,
+
+    /// Only write the failed assertions (the summary still counts all of them)
+    #[arg(long)]
+    pub only_failed: bool,
+
+    /// With --only-failed, also write passing assertions that were never hit
+    #[arg(long, requires = "only_failed")]
+    pub with_unhit: bool,
+
+    /// Also append a Markdown summary to the file named by $GITHUB_STEP_SUMMARY
+    #[arg(long)]
+    pub github_summary: bool,
+
+    /// Also annotate the Buildkite build with a Markdown summary, through
+    /// buildkite-agent, styled `error`, `warning` or `success` by the results
+    #[arg(long)]
+    pub buildkite_annotate: bool,
+
+    /// Also write the failures as VS Code diagnostics (JSON) to this file,
+    /// e.g. `.vscode/crunch-diagnostics.json`
+    #[arg(long, value_name = "FILE")]
+    pub vscode_diagnostics: Option<PathBuf>,
+
+    /// Which results make crunch exit with a non-zero status [default: none]
+    #[arg(long, value_enum, env = "CRUNCH_FAIL_ON")]
+    pub fail_on: Option<FailOn>,
+
+    /// Name of this run in the report's run_info, e.g. the build it tested
+    #[arg(long, value_name = "ID", env = "CRUNCH_RUN_ID")]
+    pub run_id: Option<String>,
+
+    /// Tag the report's run_info with this label (repeatable)
+    #[arg(long = "label", value_name = "KEY=VALUE", value_parser = parse_label)]
+    pub labels: Vec<(String, String)>,
+
+    /// Don't record the git commit and branch of the working directory
+    #[arg(long)]
+    pub no_git: bool,
+
+    /// SQLite database to add this run's results to, for `crunch history`
+    #[arg(long, value_name = "DB", env = "CRUNCH_HISTORY")]
+    pub history: Option<String>,
+
+    /// `json` or `jsonl` report of an earlier run: exit with status 2 only
+    /// when an assertion fails that didn't fail there, instead of --fail-on
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// Replace the --baseline report with this run's (as `json`), creating it
+    /// if it doesn't exist yet
+    #[arg(long, requires = "baseline")]
+    pub update_baseline: bool,
+}
+
+fn parse_label(label: &str) -> Result<(String, String), String> {
+    match label.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{}`", label)),
+    }
+}
+
+impl EvaluateArgs {
+    /// Where to write the results.
+    ///
+    /// For compatibility with `crunch <input> <output>`, see
+    /// [`legacy_output`](Self::legacy_output). Otherwise every path is an input.
+    pub fn take_output(&mut self) -> String {
+        if let Some(output) = self.output.take() {
+            return output;
+        }
+        if self.legacy_output().is_some() {
+            return self.input.inputs.pop().unwrap();
+        }
+        crunch::output::STDOUT.to_string()
+    }
+
+    /// The output of `crunch <input> <output>`: without --output, the second
+    /// of exactly two paths unless it is SDK output itself.
+    pub fn legacy_output(&self) -> Option<&str> {
+        match &self.input.inputs[..] {
+            [_, output] if self.output.is_none() && !crunch::input::is_sdk_input(output) => Some(output),
+            _ => None,
+        }
+    }
+
+    pub fn output_options(&self) -> Options {
+        Options {
+            color: false,
+            group_by: self.group_by,
+            top: self.top,
+            source_root: self.source_root.clone(),
+            repo: self.repo_url.clone().map(|url| RepoLink { url, rev: self.rev.clone().unwrap_or_else(|| "HEAD".to_string()) }),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SummaryArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    #[command(flatten)]
+    pub sort: SortArgs,
+
+    /// When to color the table
+    #[arg(long, value_enum, default_value_t = Color::Auto)]
+    pub color: Color,
+
+    /// Nest the assertions under their file, class, function or display-type
+    #[arg(long)]
+    pub group_by: Option<GroupBy>,
+
+    /// List the N failed assertions with the most counterexample hits
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// TOML file of assertion `ids` and `regexes` whose failures are expected
+    #[arg(long, value_name = "FILE")]
+    pub xfail: Option<PathBuf>,
+
+    /// TOML file mapping display types to error, warning or info
+    #[arg(long, value_name = "FILE")]
+    pub severity: Option<PathBuf>,
+}
+
+impl SummaryArgs {
+    pub fn output_options(&self) -> Options {
+        Options { color: self.color.enabled(), group_by: self.group_by, top: self.top, source_root: None, repo: None }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    /// Only when writing to a terminal and $NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    pub fn enabled(self) -> bool {
+        match self {
+            Color::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|x| x.is_empty()),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// SQLite database to add this run to, created if it doesn't exist
+    #[arg(long, value_name = "DB", required_unless_present = "parquet_hits")]
+    pub sqlite: Option<String>,
+
+    /// Parquet file to write every assertion hit to
+    #[arg(long, value_name = "FILE")]
+    pub parquet_hits: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Also check each line against the bundled JSON Schema of the SDK output,
+    /// reporting the path of every field that doesn't match
+    #[arg(long)]
+    pub validate_schema: bool,
+
+    /// Fail when a registered assertion was never hit (`never_exercised`),
+    /// instead of only warning, to catch dead instrumentation
+    #[arg(long)]
+    pub fail_on_never_exercised: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EventsArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// File to write the events to, `-` for stdout (the default)
+    #[arg(short, long, default_value = crunch::output::STDOUT)]
+    pub output: String,
+
+    /// How to write the events
+    #[arg(short, long, value_enum, default_value_t = EventFormat::Jsonl)]
+    pub format: EventFormat,
+
+    /// Only extract events whose name matches this glob (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub name: Vec<Pattern>,
+
+    #[command(flatten)]
+    pub events: EventFilterArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventFormat {
+    /// One JSON object per line for each event
+    Jsonl,
+    /// Comma separated values, with the details as JSON
+    Csv,
+    /// Tab separated values, with the details as JSON
+    Tsv,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// The earlier run
+    #[arg(value_name = "BEFORE")]
+    pub before: String,
+
+    /// The later run
+    #[arg(value_name = "AFTER")]
+    pub after: String,
+
+    /// File to write the differences to, `-` for stdout (the default)
+    #[arg(short, long, default_value = crunch::output::STDOUT)]
+    pub output: String,
+
+    /// How to write the differences
+    #[arg(short, long, value_enum, default_value_t = TextFormat::Text)]
+    pub format: TextFormat,
+
+    /// Exit with status 2 when an assertion newly fails, or was added and fails
+    #[arg(long)]
+    pub fail_on_regression: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// The reports to combine, a counterexample in any of them fails an
+    /// assertion and an example in any of them satisfies a Sometimes assertion
+    #[arg(value_name = "REPORT", required = true)]
+    pub reports: Vec<String>,
+
+    /// File to write the combined report to, `-` for stdout (the default)
+    #[arg(short, long, default_value = crunch::output::STDOUT)]
+    pub output: String,
+
+    /// How to write the combined report [default: jsonl]
+    #[arg(short, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Which results make crunch exit with a non-zero status [default: none]
+    #[arg(long, value_enum)]
+    pub fail_on: Option<FailOn>,
+}
+
+#[derive(Args, Debug)]
+pub struct FlakyArgs {
+    /// One SDK output or `json`/`jsonl` report per run
+    #[arg(value_name = "RUN", required = true)]
+    pub runs: Vec<String>,
+
+    /// File to write the matrix to, `-` for stdout (the default)
+    #[arg(short, long, default_value = crunch::output::STDOUT)]
+    pub output: String,
+
+    /// How to write the matrix
+    #[arg(short, long, value_enum, default_value_t = TextFormat::Text)]
+    pub format: TextFormat,
+
+    /// Only list the assertions that both passed and failed
+    #[arg(long)]
+    pub only_flaky: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// The id of the assertion
+    pub id: String,
+
+    /// SQLite database the runs were recorded into
+    #[arg(long, value_name = "DB", env = "CRUNCH_HISTORY")]
+    pub db: String,
+
+    /// How many of the latest runs to show
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub last: usize,
+
+    /// How to write the history
+    #[arg(short, long, value_enum, default_value_t = TextFormat::Text)]
+    pub format: TextFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct TailArgs {
+    /// SDK output file or named pipe to follow, read from the start,
+    /// or `unix:PATH` to connect to a Unix domain socket
+    #[arg(value_name = "INPUT")]
+    pub input: String,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// File to keep rewriting with the report
+    #[arg(short, long)]
+    pub output: String,
+
+    /// How to write the report
+    #[arg(short, long, value_enum, default_value_t = Format::Json)]
+    pub format: Format,
+
+    /// How often to check for new lines and rewrite the report, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    pub interval: u64,
+
+    /// Stop once nothing was appended for this long, in seconds,
+    /// instead of following the input until interrupted
+    #[arg(long, value_name = "SECONDS")]
+    pub idle_timeout: Option<u64>,
+
+    /// Top-level field added to each line with the time it was written,
+    /// as seconds since the epoch or RFC 3339 (e.g. `ts`)
+    #[arg(long, value_name = "FIELD", env = "CRUNCH_TIMESTAMP_FIELD")]
+    pub timestamp_field: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// Directory to keep the merged report in, as `report.<extension>`
+    /// (or the files of a directory format)
+    #[arg(short, long, value_name = "DIR")]
+    pub output: PathBuf,
+
+    /// How to write the merged report
+    #[arg(short, long, value_enum, default_value_t = Format::Json)]
+    pub format: Format,
+
+    /// How long to wait for more changes after one before rereading
+    /// the files, in milliseconds
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 500)]
+    pub settle: u64,
+
+    /// Stop once no file changed for this long, in seconds,
+    /// instead of watching until interrupted
+    #[arg(long, value_name = "SECONDS")]
+    pub idle_timeout: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct ListenArgs {
+    /// Address to accept connections on, e.g. `0.0.0.0:9123`
+    #[arg(value_name = "ADDRESS")]
+    pub address: String,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// File to keep rewriting with the report
+    #[arg(short, long)]
+    pub output: String,
+
+    /// How to write the report
+    #[arg(short, long, value_enum, default_value_t = Format::Json)]
+    pub format: Format,
+
+    /// How often to rewrite the report if anything was received, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    pub interval: u64,
+
+    /// Stop once nothing was received for this long, in seconds,
+    /// instead of listening until interrupted
+    #[arg(long, value_name = "SECONDS")]
+    pub idle_timeout: Option<u64>,
+
+    /// Top-level field added to each line with the time it was written,
+    /// as seconds since the epoch or RFC 3339 (e.g. `ts`)
+    #[arg(long, value_name = "FIELD", env = "CRUNCH_TIMESTAMP_FIELD")]
+    pub timestamp_field: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// The `json` or `jsonl` report to show, read again on each request
+    #[arg(value_name = "REPORT", conflicts_with = "ingest")]
+    pub report: Option<String>,
+
+    /// Accept SDK output as newline delimited JSON on `POST /lines`,
+    /// evaluated together with everything posted before
+    #[arg(long)]
+    pub ingest: bool,
+
+    /// Address to serve on
+    #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1:8080")]
+    pub address: String,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// Top-level field added to each line with the time it was written,
+    /// as seconds since the epoch or RFC 3339 (e.g. `ts`)
+    #[arg(long, value_name = "FIELD", env = "CRUNCH_TIMESTAMP_FIELD")]
+    pub timestamp_field: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct TuiArgs {
+    /// A `json` or `jsonl` report, or SDK output to evaluate
+    #[arg(value_name = "INPUT", default_value = crunch::input::STDIN)]
+    pub input: String,
+
+    /// When evaluating SDK output, list up to N distinct example and
+    /// counterexample details per assertion
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    pub keep_examples: usize,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Plain text, for reading in a terminal
+    Text,
+    /// A single JSON document
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct SchemaArgs {
+    /// Which document to print the schema of
+    #[arg(value_enum, default_value_t = SchemaDocument::Report)]
+    pub document: SchemaDocument,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaDocument {
+    /// The `json` and `yaml` reports
+    Report,
+    /// One evaluated assertion, as written on each line of the `jsonl` report
+    Assertion,
+    /// One line of SDK output, as checked by `validate --validate-schema`
+    Input,
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// One JSON object per line for each assertion
+    Jsonl,
+    /// A single JSON document with a summary and all assertions
+    Json,
+    /// The same document as `json`, in YAML
+    Yaml,
+    /// JUnit XML, one test suite per source file
+    Junit,
+    /// Test Anything Protocol, one test point per assertion
+    Tap,
+    /// Markdown tables of failed and passed assertions
+    Markdown,
+    /// Comma separated values, one row per assertion
+    Csv,
+    /// Tab separated values, one row per assertion
+    Tsv,
+    /// SARIF for code scanning, one result per failed Always/Reachability assertion
+    Sarif,
+    /// Parquet, one row per assertion
+    Parquet,
+    /// Compiler-style `file:line:column: error: message` lines, one per
+    /// failure, e.g. for a VS Code problem matcher
+    Diagnostics,
+    /// Checkstyle XML, one error per failure, e.g. for Jenkins Warnings NG or reviewdog
+    Checkstyle,
+    /// GitLab Code Quality JSON, one issue per failure
+    Codequality,
+    /// Allure results, a directory (--output) with a result file per assertion
+    Allure,
+    /// TeamCity service messages, one test per assertion
+    Teamcity,
+    /// Markdown for `buildkite-agent annotate`
+    Buildkite,
+    /// JUnit XML in the directory layout of CircleCI's store_test_results (--output)
+    Circleci,
+    /// Azure Pipelines logging commands, a logged issue per failure
+    Azdo,
+    /// Plain text without color, a block per assertion and the totals,
+    /// for paging through with `less`
+    Text,
+}
+
+impl Format {
+    /// Whether the format writes a directory of files rather than one file.
+    pub fn writes_directory(self) -> bool {
+        matches!(self, Format::Allure | Format::Circleci)
+    }
+
+    /// The usual extension of a file in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Jsonl => "jsonl",
+            Format::Json | Format::Codequality => "json",
+            Format::Yaml => "yaml",
+            Format::Junit | Format::Checkstyle | Format::Allure | Format::Circleci => "xml",
+            Format::Tap => "tap",
+            Format::Markdown | Format::Buildkite => "md",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Sarif => "sarif",
+            Format::Parquet => "parquet",
+            Format::Diagnostics | Format::Teamcity | Format::Azdo | Format::Text => "txt",
+        }
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailOn {
+    /// Exit with status 2 when any assertion failed
+    Failed,
+    /// Like `failed`, ignoring assertions that only failed by never being
+    /// hit (`not_hit` and `not_reached`)
+    Reached,
+    /// Like `failed`, and exit with status 3 when any assertion was never hit
+    AnyUnhit,
+    /// Always exit with status 0
+    None,
+}
+
+#[derive(Args, Default, Debug)]
+pub struct InputArgs {
+    /// SDK output files to read (JSON lines), `-` or omitted for stdin.
+    /// Assertions with the same id are evaluated together across all files.
+    /// Directories and glob patterns (e.g. 'logs/**/*.jsonl') are expanded,
+    /// gzip and zstd compressed files are decompressed.
+    /// Named pipes are read until their last writer closes them, and
+    /// `unix:PATH` reads a Unix domain socket until it is closed
+    #[arg(value_name = "INPUT", env = "CRUNCH_INPUT", default_value = crunch::input::STDIN)]
+    pub inputs: Vec<String>,
+
+    /// Search directory inputs recursively
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Only read files from directories whose name matches
+    /// (default: *.json, *.jsonl, optionally compressed as .gz or .zst)
+    #[arg(long, value_name = "PATTERN")]
+    pub include: Vec<Pattern>,
+
+    /// Skip files from directories and globs whose name matches
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Vec<Pattern>,
+
+    /// Fail on the first line that is not a well-formed SDK message
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Top-level field added to each line with the time it was written,
+    /// as seconds since the epoch or RFC 3339 (e.g. `ts`)
+    #[arg(long, value_name = "FIELD", env = "CRUNCH_TIMESTAMP_FIELD")]
+    pub timestamp_field: Option<String>,
+
+    /// Prefix each assertion id with the input file it was read from
+    /// (`file:id`), so inputs that reuse an id are evaluated apart
+    #[arg(long, overrides_with = "no_namespace_by_file")]
+    pub namespace_by_file: bool,
+
+    /// Don't prefix assertion ids with their input file, even when the
+    /// config file asks for it
+    #[arg(long, overrides_with = "namespace_by_file")]
+    pub no_namespace_by_file: bool,
+
+    /// Map input files into memory and parse their lines in place instead
+    /// of reading them, faster on large files on fast disks. The files must
+    /// not be truncated while they are read
+    #[arg(long)]
+    pub mmap: bool,
+}
+
+impl InputArgs {
+    /// The input files after expanding directories and globs.
+    pub fn files(&self) -> Result<Vec<String>> {
+        let discover = Discover {
+            recursive: self.recursive,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+        };
+        discover.expand(&self.inputs)
+    }
+
+    /// Open one of the [`files`](Self::files), mapped with `--mmap`.
+    pub fn open(&self, path: &str) -> Result<Box<dyn BufRead>> {
+        if self.mmap {
+            crunch::input::open_mapped(path)
+        } else {
+            crunch::input::open(path)
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct FilterArgs {
+    /// Only evaluate these assert types (always, sometimes, reachability)
+    #[arg(long = "type", value_name = "TYPE", value_delimiter = ',')]
+    pub types: Vec<AssertType>,
+
+    /// Only evaluate assertions whose id matches this regex
+    #[arg(long, value_name = "REGEX")]
+    pub id_regex: Option<Regex>,
+
+    /// Only evaluate assertions whose message matches this regex
+    #[arg(long, value_name = "REGEX")]
+    pub message_regex: Option<Regex>,
+
+    /// Only evaluate assertions in source files matching this glob (repeatable)
+    #[arg(long = "file", value_name = "PATTERN")]
+    pub files: Vec<Pattern>,
+
+    /// Only evaluate assertions in classes matching this glob (repeatable)
+    #[arg(long = "class", value_name = "PATTERN")]
+    pub classes: Vec<Pattern>,
+
+    /// Only evaluate assertions in functions matching this glob (repeatable)
+    #[arg(long = "function", value_name = "PATTERN")]
+    pub functions: Vec<Pattern>,
+
+    /// Skip assertions whose id matches this glob (repeatable)
+    #[arg(long = "exclude-id", value_name = "PATTERN")]
+    pub exclude_ids: Vec<Pattern>,
+
+    /// Skip assertions in source files matching this glob (repeatable)
+    #[arg(long = "exclude-file", value_name = "PATTERN")]
+    pub exclude_files: Vec<Pattern>,
+}
+
+impl FilterArgs {
+    pub fn filter(&self) -> Filter {
+        Filter {
+            types: self.types.clone(),
+            id_regex: self.id_regex.clone(),
+            message_regex: self.message_regex.clone(),
+            files: self.files.clone(),
+            classes: self.classes.clone(),
+            functions: self.functions.clone(),
+            exclude_ids: self.exclude_ids.clone(),
+            exclude_files: self.exclude_files.clone(),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct EventFilterArgs {
+    /// Skip user events whose name matches this regex (e.g. `^heartbeat$`)
+    #[arg(long, value_name = "REGEX")]
+    pub event_filter: Option<Regex>,
+
+    /// Only keep the first of every N user events with the same name
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub event_sample: usize,
+}
+
+impl EventFilterArgs {
+    pub fn filter(&self) -> EventFilter {
+        EventFilter::new(self.event_filter.clone(), self.event_sample)
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SortArgs {
+    /// Order the assertions by id, file, status or type
+    #[arg(long, value_name = "KEY")]
+    pub sort: Option<SortKey>,
+
+    /// Reverse the order given by --sort
+    #[arg(long, requires = "sort")]
+    pub reverse: bool,
+}
+
+impl SortArgs {
+    pub fn apply(&self, report: &mut Report) {
+        if let Some(key) = self.sort {
+            report.sort(key, self.reverse);
+        }
+    }
+}
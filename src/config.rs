@@ -0,0 +1,155 @@
+// Project defaults read from crunch.toml, for options not given on the
+// command line.
+
+use anyhow::{ anyhow, Context, Result };
+use crunch::{ AssertType, Quarantine, Severities };
+use glob::Pattern;
+use regex::Regex;
+use serde::{ Deserialize };
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::cli::{ EvaluateArgs, FailOn, FilterArgs, Format, InputArgs };
+
+/// The name of the config file looked for in the working directory and its parents.
+pub const CONFIG_FILE: &str = "crunch.toml";
+
+/// e.g.
+///
+/// ```toml
+/// format = "junit"
+/// output = "antithesis.xml"
+/// fail-on = "failed"
+/// max-details-bytes = 65536
+/// redact = ["password", "token"]
+/// timestamp-field = "ts"
+/// namespace-by-file = true
+///
+/// [filter]
+/// types = ["always", "reachability"]
+/// exclude-files = ["vendor/**"]
+///
+/// [severity]
+/// Sometimes = "warning"
+///
+/// [xfail]
+/// ids = ["queue never drops a message"]
+/// ```
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub format: Option<Format>,
+    pub output: Option<String>,
+    pub fail_on: Option<FailOn>,
+    pub max_details_bytes: Option<usize>,
+    pub redact: Vec<String>,
+    pub timestamp_field: Option<String>,
+    pub namespace_by_file: Option<bool>,
+    pub filter: FilterConfig,
+    pub severity: Severities,
+    pub xfail: Option<Quarantine>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FilterConfig {
+    types: Vec<String>,
+    id_regex: Option<String>,
+    message_regex: Option<String>,
+    files: Vec<String>,
+    classes: Vec<String>,
+    functions: Vec<String>,
+    exclude_ids: Vec<String>,
+    exclude_files: Vec<String>,
+}
+
+impl Config {
+    /// Read `path`, or else the nearest crunch.toml, or else use no defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match discover()? {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("unable to parse {}", path.display()))
+    }
+
+    /// Fill in what was not given on the command line.
+    pub fn apply(&self, args: &mut EvaluateArgs) -> Result<()> {
+        self.filter.apply(&mut args.filter)?;
+        args.format = args.format.or(self.format);
+        args.fail_on = args.fail_on.or(self.fail_on);
+        args.max_details_bytes = args.max_details_bytes.or(self.max_details_bytes);
+        if args.redact.is_empty() {
+            args.redact.clone_from(&self.redact);
+        }
+        self.apply_input(&mut args.input);
+        if args.output.is_none() && args.legacy_output().is_none() {
+            args.output.clone_from(&self.output);
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Fill in the input options not given on the command line.
+    pub fn apply_input(&self, args: &mut InputArgs) {
+        if args.timestamp_field.is_none() {
+            args.timestamp_field.clone_from(&self.timestamp_field);
+        }
+        if !args.namespace_by_file && !args.no_namespace_by_file {
+            args.namespace_by_file = self.namespace_by_file.unwrap_or_default();
+        }
+    }
+}
+
+impl FilterConfig {
+    pub fn apply(&self, args: &mut FilterArgs) -> Result<()> {
+        if args.types.is_empty() {
+            args.types = self.types.iter().map(|x| assert_type(x)).collect::<Result<_>>()?;
+        }
+        if args.id_regex.is_none() {
+            args.id_regex = self.id_regex.as_deref().map(regex).transpose()?;
+        }
+        if args.message_regex.is_none() {
+            args.message_regex = self.message_regex.as_deref().map(regex).transpose()?;
+        }
+        for (arg, config) in [
+            (&mut args.files, &self.files),
+            (&mut args.classes, &self.classes),
+            (&mut args.functions, &self.functions),
+            (&mut args.exclude_ids, &self.exclude_ids),
+            (&mut args.exclude_files, &self.exclude_files),
+        ] {
+            if arg.is_empty() {
+                *arg = config.iter().map(|x| pattern(x)).collect::<Result<_>>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Parsed like --type, so that a misspelled type is an error rather than a
+// filter that matches nothing
+fn assert_type(s: &str) -> Result<AssertType> {
+    s.parse().map_err(|e| anyhow!("{} in the config", e))
+}
+
+fn regex(s: &str) -> Result<Regex> {
+    Regex::new(s).with_context(|| format!("invalid regex {} in the config", s))
+}
+
+fn pattern(s: &str) -> Result<Pattern> {
+    Pattern::new(s).with_context(|| format!("invalid pattern {} in the config", s))
+}
+
+// The nearest crunch.toml in the working directory or one of its parents
+fn discover() -> Result<Option<PathBuf>> {
+    let cwd = env::current_dir().context("unable to get the working directory")?;
+    Ok(cwd.ancestors().map(|x| x.join(CONFIG_FILE)).find(|x| x.is_file()))
+}
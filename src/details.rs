@@ -0,0 +1,60 @@
+// Reworking the details of evaluated assertions before they are written.
+
+use serde_json::{ json, Value };
+
+use crate::evaluate::EvaluatedAssertion;
+
+/// Replace the details whose JSON text is longer than `max_bytes` with a
+/// marker such as
+///
+/// ```json
+/// {"truncated": true, "bytes": 482113, "preview": "{\"request\":{\"body\":\"..."}
+/// ```
+///
+/// where `preview` is the first `max_bytes` of the JSON text.
+pub fn truncate_details(assertions: &mut [EvaluatedAssertion], max_bytes: usize) {
+    for evaled in assertions {
+        for details in evaled.details_mut() {
+            truncate(details, max_bytes);
+        }
+    }
+}
+
+/// What redacted values are replaced with.
+pub const REDACTED: &str = "<redacted>";
+
+/// Replace the value of every key named in `keys`, at any depth of the
+/// details, with [`REDACTED`].
+pub fn redact_details(assertions: &mut [EvaluatedAssertion], keys: &[String]) {
+    for evaled in assertions {
+        for details in evaled.details_mut() {
+            redact(details, keys);
+        }
+    }
+}
+
+fn redact(value: &mut Value, keys: &[String]) {
+    match value {
+        Value::Object(map) => for (key, value) in map {
+            if keys.contains(key) {
+                *value = Value::from(REDACTED);
+            } else {
+                redact(value, keys);
+            }
+        },
+        Value::Array(values) => for value in values {
+            redact(value, keys);
+        },
+        _ => {},
+    }
+}
+
+fn truncate(details: &mut Value, max_bytes: usize) {
+    let text = details.to_string();
+    if text.len() <= max_bytes {
+        return;
+    }
+    // Cut at a character boundary so the preview stays valid UTF-8
+    let end = (0..=max_bytes).rev().find(|x| text.is_char_boundary(*x)).unwrap_or(0);
+    *details = json!({ "truncated": true, "bytes": text.len(), "preview": &text[..end] });
+}
@@ -0,0 +1,160 @@
+// Comparing the outcomes of the assertions of two runs.
+
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+
+use crate::evaluate::{ EvaluatedAssertion, Status };
+
+/// What a report says about one assertion, which is all that is compared.
+///
+/// The other fields of a report are ignored (see
+/// [`read_assertions`](crate::read_assertions)), so reports written by older
+/// versions of crunch can still be read.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Outcome {
+    pub id: String,
+    #[serde(default)]
+    pub display_type: String,
+    #[serde(default)]
+    pub message: String,
+    pub status: Status,
+    #[serde(default)]
+    pub expected_failure: bool,
+}
+
+impl Outcome {
+    /// Whether this counts as a failure: not passed, not unevaluated and
+    /// not quarantined.
+    pub fn failed(&self) -> bool {
+        !matches!(self.status, Status::Passed | Status::Unevaluated) && !self.expected_failure
+    }
+}
+
+impl From<&EvaluatedAssertion> for Outcome {
+    fn from(evaled: &EvaluatedAssertion) -> Self {
+        Outcome {
+            id: evaled.id.clone(),
+            display_type: evaled.display_type.clone(),
+            message: evaled.message.clone(),
+            status: evaled.status,
+            expected_failure: evaled.expected_failure,
+        }
+    }
+}
+
+/// An assertion whose outcome changed between the runs.
+#[derive(Serialize, Debug)]
+pub struct Change {
+    pub id: String,
+    pub display_type: String,
+    pub message: String,
+    pub before: Status,
+    pub after: Status,
+}
+
+/// How the outcomes of the assertions changed from one run to the next.
+#[derive(Serialize, Debug, Default)]
+pub struct Diff {
+    /// Assertions that failed after but not before.
+    pub newly_failing: Vec<Change>,
+    /// Assertions that failed before but not after.
+    pub newly_passing: Vec<Change>,
+    /// Assertions only in the second run.
+    pub added: Vec<Outcome>,
+    /// Assertions only in the first run.
+    pub removed: Vec<Outcome>,
+}
+
+impl Diff {
+    /// Compare the outcomes by assertion id.
+    pub fn new(before: Vec<Outcome>, after: Vec<Outcome>) -> Self {
+        let mut before: BTreeMap<String, Outcome> = before.into_iter().map(|x| (x.id.clone(), x)).collect();
+        let mut diff = Diff::default();
+        let mut after: Vec<Outcome> = after;
+        after.sort_by(|a, b| a.id.cmp(&b.id));
+        for outcome in after {
+            let Some(old) = before.remove(&outcome.id) else {
+                diff.added.push(outcome);
+                continue;
+            };
+            let changes = match (old.failed(), outcome.failed()) {
+                (false, true) => &mut diff.newly_failing,
+                (true, false) => &mut diff.newly_passing,
+                _ => continue,
+            };
+            changes.push(Change {
+                id: outcome.id,
+                display_type: outcome.display_type,
+                message: outcome.message,
+                before: old.status,
+                after: outcome.status,
+            });
+        }
+        diff.removed = before.into_values().collect();
+        diff
+    }
+
+    /// Whether something fails now that didn't before: a newly failing
+    /// assertion, or an added assertion that fails.
+    pub fn has_regression(&self) -> bool {
+        !self.newly_failing.is_empty() || self.added.iter().any(Outcome::failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(id: &str, status: Status) -> Outcome {
+        Outcome { id: id.to_string(), display_type: "Always".to_string(), message: id.to_string(), status, expected_failure: false }
+    }
+
+    fn ids(changes: &[Change]) -> Vec<&str> {
+        changes.iter().map(|x| x.id.as_str()).collect()
+    }
+
+    #[test]
+    fn changes_are_classified() {
+        let before = vec![
+            outcome("fixed", Status::Failed),
+            outcome("broken", Status::Passed),
+            outcome("unhit", Status::Passed),
+            outcome("still failing", Status::NotHit),
+            outcome("still passing", Status::Passed),
+            outcome("gone", Status::Passed),
+            outcome("known", Status::Unevaluated),
+        ];
+        let after = vec![
+            outcome("unhit", Status::NotHit),
+            outcome("new", Status::Passed),
+            outcome("known", Status::Passed),
+            outcome("still passing", Status::Passed),
+            outcome("broken", Status::Failed),
+            outcome("still failing", Status::Failed),
+            outcome("fixed", Status::Passed),
+        ];
+        let diff = Diff::new(before, after);
+        assert_eq!(ids(&diff.newly_failing), ["broken", "unhit"]);
+        assert_eq!(ids(&diff.newly_passing), ["fixed"]);
+        assert_eq!((diff.newly_failing[1].before, diff.newly_failing[1].after), (Status::Passed, Status::NotHit));
+        assert_eq!(diff.added.iter().map(|x| x.id.as_str()).collect::<Vec<_>>(), ["new"]);
+        assert_eq!(diff.removed.iter().map(|x| x.id.as_str()).collect::<Vec<_>>(), ["gone"]);
+        assert!(diff.has_regression());
+    }
+
+    #[test]
+    fn expected_failures_are_no_regression() {
+        let mut quarantined = outcome("flaky", Status::Failed);
+        quarantined.expected_failure = true;
+        let diff = Diff::new(vec![outcome("flaky", Status::Passed)], vec![quarantined.clone()]);
+        assert!(diff.newly_failing.is_empty());
+        assert!(!diff.has_regression());
+
+        let diff = Diff::new(Vec::new(), vec![quarantined, outcome("unknown", Status::Unevaluated)]);
+        assert_eq!(diff.added.len(), 2);
+        assert!(!diff.has_regression());
+
+        let diff = Diff::new(Vec::new(), vec![outcome("new", Status::NotHit)]);
+        assert!(diff.has_regression());
+    }
+}
@@ -0,0 +1,777 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::{ Value };
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use crate::events::ContextEvent;
+use crate::guidance::GuidanceSummary;
+use crate::sdk::{ AntithesisAssert, AntithesisSdk, AssertType, Location, SDKInput };
+use crate::severity::Severity;
+use crate::timestamp::{ Span, Timestamp };
+
+/// The pass/fail verdict for all entries sharing one assertion `id`.
+///
+/// Assertions can be read back from a report, see [`read_assertions`](crate::read_assertions).
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EvaluatedAssertion {
+    pub assert_type: AssertType,
+    pub display_type: String,
+    pub id: String,
+    pub message: String,
+    pub location: Location,
+    /// The SDK that wrote the assertion, when an `antithesis_sdk` line came before it.
+    pub sdk: Option<AntithesisSdk>,
+    /// Details of a hit that satisfied the assertion, if any.
+    pub example_details: Option<Value>,
+    /// Details of a hit that violated the assertion, if any.
+    pub counter_details: Option<Value>,
+    /// Up to `keep_examples` distinct details of hits that satisfied the
+    /// assertion, in the order they were read (see [`with_examples`](Self::with_examples)).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Value>,
+    /// Up to `keep_examples` distinct details of hits that violated the assertion.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counterexamples: Vec<Value>,
+    /// For a failed assertion, the user events read just before the hit
+    /// that failed it (with `--context-events`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_events: Vec<ContextEvent>,
+    /// The guidance written for this assertion, which shares its id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guidance: Option<GuidanceSummary>,
+    /// Whether the assertion was evaluated at all during the run.
+    pub hit: bool,
+    /// How many hits had `condition == true`.
+    pub true_count: usize,
+    /// How many hits had `condition == false`.
+    pub false_count: usize,
+    /// `true_count + false_count`.
+    pub total_hits: usize,
+    /// For numeric comparisons (e.g. AlwaysGreaterThan), the smallest margin
+    /// by which a hit satisfied the comparison, negative when it didn't. For
+    /// an Always assertion this is how close it came to failing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_margin: Option<f64>,
+    /// The largest margin, for a Sometimes assertion how close it came to passing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_margin: Option<f64>,
+    /// The timestamp of the earliest hit, with `--timestamp-field`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_hit_at: Option<Timestamp>,
+    /// The timestamp of the latest hit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_hit_at: Option<Timestamp>,
+    /// For a failed assertion, when its first counterexample was read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_failure: Option<FirstFailure>,
+    pub passed: bool,
+    /// `passed`, telling apart the assertions that failed only by never
+    /// being hit from those with a counterexample.
+    pub status: Status,
+    /// What to look into for a failure that isn't a counterexample.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'static str>,
+    /// Why the assertion passed, `None` when it didn't.
+    pub pass_reason: Option<PassReason>,
+    /// Why the assertion failed, `None` when it passed or was unevaluated.
+    pub failure_reason: Option<FailureReason>,
+    /// No catalog entry was seen, the assertion info was taken from a hit instead.
+    pub catalog_missing: bool,
+    /// Other catalog entries for the same id, with a different location,
+    /// message or type. The assertion info is taken from the first one read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub catalog_conflicts: Vec<CatalogConflict>,
+    /// A failure that was quarantined, see [`Quarantine`](crate::Quarantine).
+    pub expected_failure: bool,
+    /// How much a failure matters, see [`Severities`](crate::Severities).
+    pub severity: Severity,
+    /// The assert type is [unknown](AssertType::Unknown), so the assertion
+    /// neither passed nor failed.
+    pub unevaluated: bool,
+}
+
+/// The outcome of an [`EvaluatedAssertion`].
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Passed,
+    Failed,
+    /// An Always assertion that must be hit failed without a counterexample,
+    /// because it was never hit.
+    NotHit,
+    /// A Sometimes assertion was never hit at all, so its condition was
+    /// never even tried.
+    NotReached,
+    /// The assert type is unknown.
+    Unevaluated,
+}
+
+impl Status {
+    /// The name used in reports, e.g. `not_hit`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::NotHit => "not_hit",
+            Status::NotReached => "not_reached",
+            Status::Unevaluated => "unevaluated",
+        }
+    }
+}
+
+// Hints for the statuses that point at the workload rather than the system
+const NOT_HIT_HINT: &str = "the assertion was registered but its code never ran, check that the workload exercises it";
+const NOT_REACHED_HINT: &str = "the assertion was registered but its code never ran, so its condition was never tried; check that the workload exercises it";
+
+/// Why an [`EvaluatedAssertion`] passed.
+///
+/// An AlwaysOrUnreachable assertion (Always with `must_hit == false`) passes
+/// either way, this says which.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PassReason {
+    /// An Always assertion was hit, and its condition was true every time.
+    AlwaysTrue,
+    /// A Sometimes assertion was hit with a true condition.
+    SometimesTrue,
+    /// A Reachable assertion was hit.
+    Reached,
+    /// An assertion that doesn't have to be hit (AlwaysOrUnreachable or
+    /// Unreachable) never was.
+    Unreached,
+}
+
+/// Why an [`EvaluatedAssertion`] failed.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// An Always assertion had a hit with a false condition.
+    CounterexampleFound,
+    /// An assertion that must be hit never was.
+    NeverHit,
+    /// A Sometimes assertion was hit, but never with a true condition.
+    NeverTrue,
+    /// An Unreachable assertion was hit.
+    UnexpectedlyReached,
+}
+
+impl FailureReason {
+    /// In words, e.g. `counterexample found`.
+    pub fn describe(self) -> &'static str {
+        match self {
+            FailureReason::CounterexampleFound => "counterexample found",
+            FailureReason::NeverHit => "never hit",
+            FailureReason::NeverTrue => "never true",
+            FailureReason::UnexpectedlyReached => "unexpectedly reached",
+        }
+    }
+}
+
+/// A catalog entry that doesn't match the first one read for its id.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CatalogConflict {
+    pub assert_type: AssertType,
+    pub message: String,
+    pub location: Location,
+}
+
+impl CatalogConflict {
+    fn new(kept: &AntithesisAssert, entry: &AntithesisAssert) -> Option<Self> {
+        let conflicts = kept.location != entry.location || kept.message != entry.message || kept.assert_type != entry.assert_type;
+        conflicts.then(|| CatalogConflict {
+            assert_type: entry.assert_type.clone(),
+            message: entry.message.clone(),
+            location: entry.location.clone(),
+        })
+    }
+}
+
+/// How far into the run a counterexample was read.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct FirstFailure {
+    /// The line it was read from, within its input.
+    pub line: usize,
+    /// Its timestamp, with `--timestamp-field`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at: Option<Timestamp>,
+    /// Seconds from the start of the run to `at`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_seconds: Option<f64>,
+}
+
+impl FirstFailure {
+    fn new(assert: &AntithesisAssert) -> Self {
+        FirstFailure { line: assert.line, at: assert.timestamp.clone(), after_seconds: None }
+    }
+
+    /// Whether this came before `other`: by timestamp when both have one,
+    /// and by line otherwise.
+    pub fn is_before(&self, other: &FirstFailure) -> bool {
+        match (&self.after_seconds, &other.after_seconds, &self.at, &other.at) {
+            (Some(a), Some(b), _, _) => a < b,
+            (_, _, Some(a), Some(b)) => a.seconds < b.seconds,
+            _ => self.line < other.line,
+        }
+    }
+}
+
+impl EvaluatedAssertion {
+    /// Evaluate one group of entries, as produced by [`group_asserts`].
+    ///
+    /// When the group has no catalog entry (`hit == false`), e.g. because the
+    /// catalog was written to a different file, the first hit stands in for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `assert_list` is empty.
+    pub fn new(assert_list: Vec<AntithesisAssert>) -> Self {
+        Self::with_examples(assert_list, 0)
+    }
+
+    /// Like [`new`](Self::new), also collecting up to `keep_examples`
+    /// distinct details into `examples` and `counterexamples`, e.g. to see
+    /// the different ways an Always assertion failed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `assert_list` is empty.
+    pub fn with_examples(assert_list: Vec<AntithesisAssert>, keep_examples: usize) -> Self {
+
+        let catalog_missing = assert_list.iter().all(|x| x.hit);
+        let mut catalog_entry = if catalog_missing { assert_list.first().cloned() } else { None };
+        let mut catalog_conflicts = Vec::new();
+        let mut condition_true_entry = None;
+        let mut condition_false_entry = None;
+        let mut true_details = Vec::new();
+        let mut false_details = Vec::new();
+        let mut true_count = 0;
+        let mut false_count = 0;
+        let mut min_margin: Option<f64> = None;
+        let mut max_margin: Option<f64> = None;
+
+        let mut hits = Span::default();
+        let mut first_failure: Option<FirstFailure> = None;
+        for entry in assert_list {
+            let hit = entry.hit;
+            // Hits are in the order they were read, so only a timestamp can
+            // put a later one first
+            if entry.may_fail() {
+                let failure = FirstFailure::new(&entry);
+                if first_failure.as_ref().is_none_or(|x| failure.at.is_some() && x.at.is_some() && failure.is_before(x)) {
+                    first_failure = Some(failure);
+                }
+            }
+            if hit {
+                if let Some(timestamp) = &entry.timestamp {
+                    hits.record(timestamp);
+                }
+                if let Some(margin) = margin(&entry.display_type, &entry.details) {
+                    min_margin = Some(min_margin.map_or(margin, |x| x.min(margin)));
+                    max_margin = Some(max_margin.map_or(margin, |x| x.max(margin)));
+                }
+                let condition = entry.condition;
+                if condition {
+                    true_count += 1;
+                    keep_distinct(&mut true_details, &entry.details, keep_examples);
+                    condition_true_entry = Some(entry);
+                } else {
+                    false_count += 1;
+                    keep_distinct(&mut false_details, &entry.details, keep_examples);
+                    condition_false_entry = Some(entry);
+                }
+            } else {
+                match &catalog_entry {
+                    Some(kept) => if let Some(conflict) = CatalogConflict::new(kept, &entry) {
+                        if !catalog_conflicts.contains(&conflict) {
+                            catalog_conflicts.push(conflict);
+                        }
+                    },
+                    None => catalog_entry = Some(entry),
+                }
+            }
+        }
+
+        let input_entry = catalog_entry.expect("cannot evaluate an empty group of assertions");
+
+        let hit = condition_true_entry.is_some() || condition_false_entry.is_some();
+        let passed: bool;
+        let mut context_events = Vec::new();
+        let mut pass_reason = None;
+        let mut failure_reason = None;
+        let mut example_details = None;
+        let mut counter_details = None;
+
+        match &input_entry.assert_type {
+            AssertType::Always => {
+                let must_hit = input_entry.must_hit;
+                if must_hit {
+                    passed = condition_true_entry.is_some() &&  condition_false_entry.is_none();
+                } else {
+                    passed = condition_false_entry.is_none();
+                }
+                if condition_false_entry.is_some() {
+                    failure_reason = Some(FailureReason::CounterexampleFound);
+                } else if !passed {
+                    failure_reason = Some(FailureReason::NeverHit);
+                } else if hit {
+                    pass_reason = Some(PassReason::AlwaysTrue);
+                } else {
+                    pass_reason = Some(PassReason::Unreached);
+                }
+                if let Some(entry) = &mut condition_false_entry {
+                    context_events = std::mem::take(&mut entry.context_events);
+                }
+                example_details = condition_true_entry.map(|x| x.details);
+                counter_details = condition_false_entry.map(|x| x.details);
+            },
+            AssertType::Sometimes => {
+                passed = condition_true_entry.is_some();
+                if passed {
+                    pass_reason = Some(PassReason::SometimesTrue);
+                } else {
+                    failure_reason = Some(if hit { FailureReason::NeverTrue } else { FailureReason::NeverHit });
+                }
+                example_details = condition_true_entry.map(|x| x.details);
+                // TODO Do we really want to show details for a sometimes that failed?
+                counter_details = condition_false_entry.map(|x| x.details);
+            },
+            AssertType::Reachability => {
+                let must_hit = input_entry.must_hit;
+                if must_hit {
+                    passed = hit;
+                    if passed {
+                        pass_reason = Some(PassReason::Reached);
+                    } else {
+                        failure_reason = Some(FailureReason::NeverHit);
+                    }
+                    example_details =  condition_true_entry.or(condition_false_entry).map(|x| x.details);
+                } else {
+                    passed = !hit;
+                    if passed {
+                        pass_reason = Some(PassReason::Unreached);
+                    } else {
+                        failure_reason = Some(FailureReason::UnexpectedlyReached);
+                    }
+                    let reached = condition_true_entry.or(condition_false_entry);
+                    if let Some(entry) = &reached {
+                        context_events.clone_from(&entry.context_events);
+                    }
+                    counter_details = reached.map(|x| x.details);
+                }
+                // Any hit reaches the assertion, whatever its condition
+                for details in false_details.drain(..) {
+                    keep_distinct(&mut true_details, &details, keep_examples);
+                }
+                if !must_hit {
+                    false_details = std::mem::take(&mut true_details);
+                }
+            },
+            AssertType::Unknown(_) => {
+                passed = false;
+            },
+        }
+
+        let unevaluated = matches!(input_entry.assert_type, AssertType::Unknown(_));
+        let status = status(&input_entry.assert_type, unevaluated, passed, failure_reason);
+        let hint = hint(status);
+
+        Self {
+            unevaluated,
+            status,
+            hint,
+            pass_reason,
+            assert_type: input_entry.assert_type,
+            display_type: input_entry.display_type.to_string(),
+            id: input_entry.id,
+            message: input_entry.message,
+            location: input_entry.location,
+            sdk: input_entry.sdk,
+            hit,
+            true_count,
+            false_count,
+            total_hits: true_count + false_count,
+            min_margin,
+            max_margin,
+            first_hit_at: hits.first,
+            last_hit_at: hits.last,
+            first_failure: if passed { None } else { first_failure },
+            passed,
+            failure_reason,
+            catalog_missing,
+            catalog_conflicts,
+            expected_failure: false,
+            severity: Severity::default(),
+            example_details,
+            counter_details,
+            guidance: None,
+            context_events,
+            examples: true_details,
+            counterexamples: false_details,
+        }
+    }
+
+    /// Add the result of the same assertion from another run, as if both runs
+    /// had been one: a counterexample in either fails it, and an example in
+    /// either satisfies a Sometimes assertion.
+    ///
+    /// Only the verdicts and counts are combined; where each run has its own
+    /// details, location or classification, those of `self` are kept.
+    pub fn merge(&mut self, other: EvaluatedAssertion) {
+        let hit = self.hit || other.hit;
+        let true_count = self.true_count + other.true_count;
+        let false_count = self.false_count + other.false_count;
+        // Whether a Reachability assertion must be hit shows in its reasons
+        let unreachable = [&*self, &other].iter()
+            .any(|x| x.pass_reason == Some(PassReason::Unreached) || x.failure_reason == Some(FailureReason::UnexpectedlyReached));
+        let (passed, pass_reason, failure_reason) = match self.assert_type {
+            AssertType::Always if false_count > 0 => (false, None, Some(FailureReason::CounterexampleFound)),
+            AssertType::Always if hit => (true, Some(PassReason::AlwaysTrue), None),
+            AssertType::Sometimes if true_count > 0 => (true, Some(PassReason::SometimesTrue), None),
+            AssertType::Sometimes if hit => (false, None, Some(FailureReason::NeverTrue)),
+            AssertType::Reachability if hit && unreachable => (false, None, Some(FailureReason::UnexpectedlyReached)),
+            AssertType::Reachability if hit => (true, Some(PassReason::Reached), None),
+            // Never hit in either run, or unevaluated, so both runs agree
+            _ => (self.passed, self.pass_reason, self.failure_reason),
+        };
+
+        let mut hits = Span::default();
+        for timestamp in [&self.first_hit_at, &self.last_hit_at, &other.first_hit_at, &other.last_hit_at].into_iter().flatten() {
+            hits.record(timestamp);
+        }
+        let first_failure = match (self.first_failure.take(), other.first_failure) {
+            (Some(a), Some(b)) => Some(if b.is_before(&a) { b } else { a }),
+            (a, b) => a.or(b),
+        };
+        let keep = self.examples.len().max(other.examples.len());
+        for details in &other.examples {
+            keep_distinct(&mut self.examples, details, keep);
+        }
+        let keep = self.counterexamples.len().max(other.counterexamples.len());
+        for details in &other.counterexamples {
+            keep_distinct(&mut self.counterexamples, details, keep);
+        }
+
+        self.hit = hit;
+        self.true_count = true_count;
+        self.false_count = false_count;
+        self.total_hits = true_count + false_count;
+        self.min_margin = combine(self.min_margin, other.min_margin, f64::min);
+        self.max_margin = combine(self.max_margin, other.max_margin, f64::max);
+        self.first_hit_at = hits.first;
+        self.last_hit_at = hits.last;
+        self.first_failure = if passed { None } else { first_failure };
+        self.passed = passed;
+        self.pass_reason = pass_reason;
+        self.failure_reason = failure_reason;
+        self.status = status(&self.assert_type, self.unevaluated, passed, failure_reason);
+        self.hint = hint(self.status);
+        self.catalog_missing &= other.catalog_missing;
+        self.expected_failure |= other.expected_failure;
+        self.sdk = self.sdk.take().or(other.sdk);
+        self.example_details = self.example_details.take().or(other.example_details);
+        self.counter_details = self.counter_details.take().or(other.counter_details);
+        self.guidance = self.guidance.take().or(other.guidance);
+        if self.context_events.is_empty() {
+            self.context_events = other.context_events;
+        }
+        for conflict in other.catalog_conflicts {
+            if !self.catalog_conflicts.contains(&conflict) {
+                self.catalog_conflicts.push(conflict);
+            }
+        }
+    }
+
+    /// How many hits violated the assertion: the false hits of an Always, or
+    /// every hit of a Reachability that must not be reached.
+    pub fn counterexample_hits(&self) -> usize {
+        match self.failure_reason {
+            Some(FailureReason::CounterexampleFound) => self.false_count,
+            Some(FailureReason::UnexpectedlyReached) => self.total_hits,
+            _ => 0,
+        }
+    }
+
+    /// All the details kept for this assertion, e.g. for rewriting them before output.
+    pub fn details_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.example_details.iter_mut()
+            .chain(self.counter_details.iter_mut())
+            .chain(self.examples.iter_mut())
+            .chain(self.counterexamples.iter_mut())
+            .chain(self.context_events.iter_mut().map(|x| &mut x.details))
+    }
+}
+
+fn status(assert_type: &AssertType, unevaluated: bool, passed: bool, failure_reason: Option<FailureReason>) -> Status {
+    match failure_reason {
+        _ if unevaluated => Status::Unevaluated,
+        _ if passed => Status::Passed,
+        Some(FailureReason::NeverHit) if *assert_type == AssertType::Always => Status::NotHit,
+        Some(FailureReason::NeverHit) if *assert_type == AssertType::Sometimes => Status::NotReached,
+        _ => Status::Failed,
+    }
+}
+
+fn hint(status: Status) -> Option<&'static str> {
+    match status {
+        Status::NotHit => Some(NOT_HIT_HINT),
+        Status::NotReached => Some(NOT_REACHED_HINT),
+        _ => None,
+    }
+}
+
+// How far a numeric comparison hit was from failing, from the `left` and
+// `right` the SDK puts in its details. A margin of 0 fails the strict
+// comparisons (GreaterThan, LessThan) but not the OrEqualTo ones.
+fn margin(display_type: &str, details: &Value) -> Option<f64> {
+    let comparison = display_type.to_ascii_lowercase().replace('_', "");
+    let left = details.get("left")?.as_f64()?;
+    let right = details.get("right")?.as_f64()?;
+    if comparison.contains("greaterthan") {
+        Some(left - right)
+    } else if comparison.contains("lessthan") {
+        Some(right - left)
+    } else {
+        None
+    }
+}
+
+// Combine two optional values with `f`, or take whichever is there
+fn combine(a: Option<f64>, b: Option<f64>, f: fn(f64, f64) -> f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+// Add `details` unless it is already there or `kept` is full
+fn keep_distinct(kept: &mut Vec<Value>, details: &Value, keep: usize) {
+    if kept.len() < keep && !kept.contains(details) {
+        kept.push(details.clone());
+    }
+}
+
+/// The entries of each assertion by id, see [`group_asserts`].
+///
+/// Hashed with aHash, which is much faster on the short ids than the
+/// default hasher and still safe against crafted input.
+pub type Groups = HashMap<String, Vec<AntithesisAssert>, ahash::RandomState>;
+
+/// Collect the `AntithesisAssert` entries by `id`, dropping all other inputs.
+///
+/// Entries without an id get their [`group_id`](AntithesisAssert::group_id)
+/// as id, so each location is evaluated on its own.
+pub fn group_asserts<I: IntoIterator<Item = SDKInput>>(inputs: I) -> Groups {
+    group_asserts_with_capacity(inputs, 0)
+}
+
+/// Like [`group_asserts`], with room for `capacity` ids up front, e.g. as
+/// guessed from the size of the input.
+pub fn group_asserts_with_capacity<I: IntoIterator<Item = SDKInput>>(inputs: I, capacity: usize) -> Groups {
+    let mut result = Groups::with_capacity_and_hasher(capacity, ahash::RandomState::new());
+    for input in inputs {
+        // Other inputs are collected separately, see Guidance, Events and RunInfo
+        if let SDKInput::AntithesisAssert(mut x) = input {
+            if x.id.is_empty() {
+                x.id = x.group_id().into_owned();
+            }
+            // The id is only copied into the map for the first entry
+            match result.get_mut(&x.id) {
+                Some(entries) => entries.push(x),
+                None => {
+                    result.insert(x.id.clone(), vec![x]);
+                },
+            }
+        }
+    }
+    result
+}
+
+/// Add the groups of `from` to `into`, e.g. to evaluate several inputs together.
+pub fn merge_groups(into: &mut Groups, from: Groups) {
+    into.reserve(from.len());
+    for (id, mut asserts) in from {
+        into.entry(id).or_default().append(&mut asserts);
+    }
+}
+
+/// Combine the assertions of several runs (e.g. read back from their reports)
+/// into one per id, see [`EvaluatedAssertion::merge`].
+pub fn merge_evaluated<I: IntoIterator<Item = EvaluatedAssertion>>(runs: I) -> Vec<EvaluatedAssertion> {
+    let mut by_id: HashMap<String, EvaluatedAssertion> = HashMap::new();
+    for mut evaled in runs {
+        match by_id.entry(evaled.id.clone()) {
+            Entry::Occupied(mut merged) => merged.get_mut().merge(evaled),
+            Entry::Vacant(entry) => {
+                // Not written to reports, so put back what the status implies
+                evaled.hint = hint(evaled.status);
+                entry.insert(evaled);
+            },
+        }
+    }
+    by_id.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ parse_line, Outcome, Quarantine };
+
+    fn entry(id: &str, assert_type: &str, must_hit: bool, hit: bool, condition: bool) -> AntithesisAssert {
+        let line = format!(
+            r#"{{"antithesis_assert": {{"id": "{}", "message": "{}", "assert_type": "{}", "display_type": "{}", "condition": {}, "hit": {}, "must_hit": {}, "location": {{"file": "src/a.rs"}}, "details": null}}}}"#,
+            id, id, assert_type, assert_type, condition, hit, must_hit,
+        );
+        match parse_line(&line).unwrap() {
+            SDKInput::AntithesisAssert(x) => x,
+            x => panic!("not an assertion: {:?}", x),
+        }
+    }
+
+    // The catalog entry of `id` followed by a hit for each condition
+    fn evaluate(id: &str, assert_type: &str, must_hit: bool, conditions: &[bool]) -> EvaluatedAssertion {
+        let mut entries = vec![entry(id, assert_type, must_hit, false, false)];
+        entries.extend(conditions.iter().map(|x| entry(id, assert_type, must_hit, true, *x)));
+        EvaluatedAssertion::new(entries)
+    }
+
+    fn verdict(evaled: &EvaluatedAssertion) -> (bool, Status, Option<PassReason>, Option<FailureReason>) {
+        (evaled.passed, evaled.status, evaled.pass_reason, evaled.failure_reason)
+    }
+
+    #[test]
+    fn always() {
+        let evaled = evaluate("a", "always", true, &[true, true]);
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::AlwaysTrue), None));
+        assert_eq!((evaled.true_count, evaled.false_count, evaled.total_hits), (2, 0, 2));
+
+        let evaled = evaluate("a", "always", true, &[true, false, false]);
+        assert_eq!(verdict(&evaled), (false, Status::Failed, None, Some(FailureReason::CounterexampleFound)));
+        assert_eq!(evaled.counterexample_hits(), 2);
+
+        let evaled = evaluate("a", "always", true, &[]);
+        assert_eq!(verdict(&evaled), (false, Status::NotHit, None, Some(FailureReason::NeverHit)));
+        assert_eq!(evaled.hint, Some(NOT_HIT_HINT));
+
+        // AlwaysOrUnreachable
+        let evaled = evaluate("a", "always", false, &[]);
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::Unreached), None));
+    }
+
+    #[test]
+    fn sometimes() {
+        let evaled = evaluate("s", "sometimes", true, &[false, true]);
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::SometimesTrue), None));
+
+        let evaled = evaluate("s", "sometimes", true, &[false, false]);
+        assert_eq!(verdict(&evaled), (false, Status::Failed, None, Some(FailureReason::NeverTrue)));
+        assert_eq!(evaled.counterexample_hits(), 0);
+
+        let evaled = evaluate("s", "sometimes", true, &[]);
+        assert_eq!(verdict(&evaled), (false, Status::NotReached, None, Some(FailureReason::NeverHit)));
+        assert_eq!(evaled.hint, Some(NOT_REACHED_HINT));
+    }
+
+    #[test]
+    fn reachable() {
+        // Any hit reaches it, whatever its condition
+        let evaled = evaluate("r", "reachability", true, &[false]);
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::Reached), None));
+
+        let evaled = evaluate("r", "reachability", true, &[]);
+        assert_eq!(verdict(&evaled), (false, Status::Failed, None, Some(FailureReason::NeverHit)));
+    }
+
+    #[test]
+    fn unreachable() {
+        let evaled = evaluate("u", "reachability", false, &[]);
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::Unreached), None));
+
+        let evaled = evaluate("u", "reachability", false, &[true, false]);
+        assert_eq!(verdict(&evaled), (false, Status::Failed, None, Some(FailureReason::UnexpectedlyReached)));
+        assert_eq!(evaled.counterexample_hits(), 2);
+    }
+
+    #[test]
+    fn expected_failure() {
+        let quarantine: Quarantine = toml::from_str(r#"ids = ["a", "b"]"#).unwrap();
+        let mut assertions = vec![
+            evaluate("a", "always", true, &[false]),
+            evaluate("b", "always", true, &[true]),
+            evaluate("c", "always", true, &[false]),
+        ];
+        let unexpected_passes: Vec<String> = quarantine.apply(&mut assertions).into_iter().map(|x| x.id.clone()).collect();
+        assert_eq!(unexpected_passes, ["b"]);
+
+        let expected: Vec<bool> = assertions.iter().map(|x| x.expected_failure).collect();
+        assert_eq!(expected, [true, false, false]);
+        // Still failed, but it doesn't count against the run
+        assert_eq!(assertions[0].status, Status::Failed);
+        let failed: Vec<bool> = assertions.iter().map(|x| Outcome::from(x).failed()).collect();
+        assert_eq!(failed, [false, false, true]);
+    }
+
+    #[test]
+    fn unknown_type() {
+        let evaled = evaluate("n", "never", true, &[true, false]);
+        assert!(evaled.unevaluated);
+        assert_eq!(evaled.assert_type, AssertType::Unknown("never".to_string()));
+        assert_eq!(verdict(&evaled), (false, Status::Unevaluated, None, None));
+        assert!(!Outcome::from(&evaled).failed());
+    }
+
+    #[test]
+    fn hits_stand_in_for_a_missing_catalog() {
+        let evaled = EvaluatedAssertion::new(vec![entry("a", "always", true, true, true)]);
+        assert!(evaled.catalog_missing);
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::AlwaysTrue), None));
+    }
+
+    #[test]
+    fn merge_runs() {
+        let merged = |a: EvaluatedAssertion, b: EvaluatedAssertion| {
+            let mut merged = merge_evaluated([a, b]);
+            assert_eq!(merged.len(), 1);
+            merged.pop().unwrap()
+        };
+
+        // A counterexample in either run fails an Always assertion
+        let evaled = merged(evaluate("a", "always", true, &[true]), evaluate("a", "always", true, &[false]));
+        assert_eq!(verdict(&evaled), (false, Status::Failed, None, Some(FailureReason::CounterexampleFound)));
+        assert_eq!((evaled.true_count, evaled.false_count, evaled.total_hits), (1, 1, 2));
+
+        // A hit in either run is enough for the other's never hit
+        let evaled = merged(evaluate("a", "always", true, &[]), evaluate("a", "always", true, &[true]));
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::AlwaysTrue), None));
+        assert_eq!(evaled.hint, None);
+
+        // An example in either run satisfies a Sometimes assertion
+        let evaled = merged(evaluate("s", "sometimes", true, &[false]), evaluate("s", "sometimes", true, &[true]));
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::SometimesTrue), None));
+
+        let evaled = merged(evaluate("s", "sometimes", true, &[]), evaluate("s", "sometimes", true, &[]));
+        assert_eq!(verdict(&evaled), (false, Status::NotReached, None, Some(FailureReason::NeverHit)));
+        assert_eq!(evaled.hint, Some(NOT_REACHED_HINT));
+
+        let evaled = merged(evaluate("r", "reachability", true, &[]), evaluate("r", "reachability", true, &[true]));
+        assert_eq!(verdict(&evaled), (true, Status::Passed, Some(PassReason::Reached), None));
+
+        // Reaching it in one run fails an unreachable assertion
+        let evaled = merged(evaluate("u", "reachability", false, &[]), evaluate("u", "reachability", false, &[true]));
+        assert_eq!(verdict(&evaled), (false, Status::Failed, None, Some(FailureReason::UnexpectedlyReached)));
+
+        let evaled = merged(evaluate("n", "never", true, &[true]), evaluate("n", "never", true, &[false]));
+        assert_eq!(verdict(&evaled), (false, Status::Unevaluated, None, None));
+    }
+
+    #[test]
+    fn merge_keeps_one_assertion_per_id() {
+        let runs = [
+            evaluate("a", "always", true, &[true]),
+            evaluate("b", "sometimes", true, &[true]),
+            evaluate("a", "always", true, &[true]),
+        ];
+        let mut merged: Vec<(String, usize)> = merge_evaluated(runs).into_iter().map(|x| (x.id, x.total_hits)).collect();
+        merged.sort();
+        assert_eq!(merged, [("a".to_string(), 2), ("b".to_string(), 1)]);
+    }
+}
@@ -0,0 +1,149 @@
+// Aggregating the user events written by `send_event()`.
+
+use serde::{ Deserialize, Serialize };
+use serde_json::{ Value };
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::lint::Source;
+use crate::sdk::SDKInput;
+
+// How many distinct payloads are kept for each event name
+const EXAMPLES_PER_EVENT: usize = 3;
+
+// The fields of an event's details that are taken as its timestamp, in order
+const TIMESTAMP_FIELDS: [&str; 3] = ["timestamp", "ts", "time"];
+
+/// How often one event was sent over the run.
+#[derive(Serialize, Clone, Debug)]
+pub struct EventSummary {
+    pub event_name: String,
+    pub count: usize,
+    /// The first few distinct payloads, in the order they were read.
+    pub examples: Vec<Value>,
+}
+
+/// Collects the `SendEvent` lines of one or more inputs.
+#[derive(Default, Clone)]
+pub struct Events {
+    by_name: BTreeMap<String, EventSummary>,
+}
+
+impl Events {
+    /// Keep `input` if it is a user event.
+    pub fn record(&mut self, input: &SDKInput) {
+        let SDKInput::SendEvent{ event_name, details } = input else {
+            return;
+        };
+        let summary = self.by_name.entry(event_name.clone()).or_insert_with(|| EventSummary {
+            event_name: event_name.clone(),
+            count: 0,
+            examples: Vec::new(),
+        });
+        summary.count += 1;
+        if summary.examples.len() < EXAMPLES_PER_EVENT && !summary.examples.contains(details) {
+            summary.examples.push(details.clone());
+        }
+    }
+
+    /// Add what `other` collected, e.g. from another input.
+    pub fn merge(&mut self, other: Events) {
+        for (name, theirs) in other.by_name {
+            let summary = self.by_name.entry(name).or_insert_with(|| EventSummary {
+                event_name: theirs.event_name.clone(),
+                count: 0,
+                examples: Vec::new(),
+            });
+            summary.count += theirs.count;
+            for example in theirs.examples {
+                if summary.examples.len() < EXAMPLES_PER_EVENT && !summary.examples.contains(&example) {
+                    summary.examples.push(example);
+                }
+            }
+        }
+    }
+
+    /// The events by name.
+    pub fn finish(self) -> Vec<EventSummary> {
+        self.by_name.into_values().collect()
+    }
+}
+
+/// A user event read shortly before a failing hit, see
+/// [`ParsedLines::context_events`](crate::ParsedLines::context_events).
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ContextEvent {
+    /// The line the event was read from, in the same input as the hit.
+    pub line: usize,
+    pub event_name: String,
+    pub details: Value,
+}
+
+/// One user event, and where it was read.
+#[derive(Serialize, Debug)]
+pub struct TimelineEvent {
+    pub file: String,
+    pub line: usize,
+    pub event_name: String,
+    /// The event's own timestamp, when its details have one that is a
+    /// number or a string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Value>,
+    pub details: Value,
+}
+
+impl TimelineEvent {
+    /// The event read from `source`, if `input` is a user event.
+    pub fn new(source: &Source, input: &SDKInput) -> Option<Self> {
+        let SDKInput::SendEvent{ event_name, details } = input else {
+            return None;
+        };
+        let timestamp = TIMESTAMP_FIELDS.iter()
+            .filter_map(|x| details.get(x))
+            .find(|x| x.is_number() || x.is_string())
+            .cloned();
+        Some(TimelineEvent {
+            file: source.file.clone(),
+            line: source.line,
+            event_name: event_name.clone(),
+            timestamp,
+            details: details.clone(),
+        })
+    }
+}
+
+/// Collects every `SendEvent` line of one or more inputs, in order.
+#[derive(Default)]
+pub struct Timeline {
+    events: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    /// Keep `input` if it is a user event.
+    pub fn record(&mut self, source: &Source, input: &SDKInput) {
+        self.events.extend(TimelineEvent::new(source, input));
+    }
+
+    /// Add the events `other` collected, e.g. from a later input.
+    pub fn merge(&mut self, mut other: Timeline) {
+        self.events.append(&mut other.events);
+    }
+
+    /// The events by timestamp when they all have a comparable one (all
+    /// numbers or all strings, e.g. RFC 3339), and in input order otherwise.
+    pub fn finish(mut self) -> Vec<TimelineEvent> {
+        let timestamps: Option<Vec<&Value>> = self.events.iter().map(|x| x.timestamp.as_ref()).collect();
+        let comparable = timestamps.is_some_and(|x| x.iter().all(|x| x.is_number()) || x.iter().all(|x| x.is_string()));
+        if comparable {
+            self.events.sort_by(|a, b| compare(a.timestamp.as_ref().unwrap(), b.timestamp.as_ref().unwrap()));
+        }
+        self.events
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+    }
+}
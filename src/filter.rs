@@ -0,0 +1,93 @@
+// Selecting which assertions are evaluated at all, and which user events
+// are kept.
+
+use glob::Pattern;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::sdk::{ AntithesisAssert, AssertType, SDKInput };
+
+/// Which assertions to keep while grouping. Assertions that are filtered out
+/// are dropped as they are read, so they never take up memory.
+///
+/// The default filter keeps everything.
+#[derive(Default, Debug)]
+pub struct Filter {
+    /// Only keep assertions of these types, all types when empty.
+    pub types: Vec<AssertType>,
+    /// Only keep assertions whose id matches somewhere.
+    pub id_regex: Option<Regex>,
+    /// Only keep assertions whose message matches somewhere.
+    pub message_regex: Option<Regex>,
+    /// Only keep assertions in a file matching one of these, any file when empty.
+    pub files: Vec<Pattern>,
+    /// Only keep assertions in a class matching one of these, any class when empty.
+    pub classes: Vec<Pattern>,
+    /// Only keep assertions in a function matching one of these, any function when empty.
+    pub functions: Vec<Pattern>,
+    /// Drop assertions whose id matches any of these.
+    pub exclude_ids: Vec<Pattern>,
+    /// Drop assertions in a file matching any of these.
+    pub exclude_files: Vec<Pattern>,
+}
+
+// An empty list of patterns matches everything
+fn any_match(patterns: &[Pattern], s: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|x| x.matches(s))
+}
+
+impl Filter {
+    /// Whether `assert` should be evaluated.
+    pub fn matches(&self, assert: &AntithesisAssert) -> bool {
+        (self.types.is_empty() || self.types.contains(&assert.assert_type))
+            && self.id_regex.as_ref().is_none_or(|x| x.is_match(&assert.id))
+            && self.message_regex.as_ref().is_none_or(|x| x.is_match(&assert.message))
+            && any_match(&self.files, assert.location.file())
+            && any_match(&self.classes, assert.location.class())
+            && any_match(&self.functions, assert.location.function())
+            && !self.exclude_ids.iter().any(|x| x.matches(&assert.id))
+            && !self.exclude_files.iter().any(|x| x.matches(assert.location.file()))
+    }
+
+    /// Whether `input` should be passed on to [`group_asserts`](crate::group_asserts),
+    /// inputs other than assertions are always kept.
+    pub fn keeps(&self, input: &SDKInput) -> bool {
+        match input {
+            SDKInput::AntithesisAssert(x) => self.matches(x),
+            _ => true,
+        }
+    }
+}
+
+/// Which user events to keep while parsing, so that noisy ones (e.g.
+/// heartbeats) are neither aggregated nor attached to failures.
+///
+/// The default filter keeps everything.
+#[derive(Default, Clone, Debug)]
+pub struct EventFilter {
+    exclude: Option<Regex>,
+    sample: usize,
+    seen: HashMap<String, usize>,
+}
+
+impl EventFilter {
+    /// Drop the events whose name matches `exclude`, and keep only the
+    /// first of every `sample` events of each remaining name.
+    pub fn new(exclude: Option<Regex>, sample: usize) -> Self {
+        EventFilter { exclude, sample, seen: HashMap::new() }
+    }
+
+    /// Whether the next event named `event_name` should be kept.
+    pub fn keeps(&mut self, event_name: &str) -> bool {
+        if self.exclude.as_ref().is_some_and(|x| x.is_match(event_name)) {
+            return false;
+        }
+        if self.sample <= 1 {
+            return true;
+        }
+        let seen = self.seen.entry(event_name.to_string()).or_default();
+        let keep = seen.is_multiple_of(self.sample);
+        *seen += 1;
+        keep
+    }
+}
@@ -0,0 +1,62 @@
+// Comparing the outcomes of the assertions over several runs, to tell
+// assertions whose result varies from those that always fail.
+
+use serde::{ Serialize };
+use std::collections::BTreeMap;
+
+use crate::diff::Outcome;
+use crate::evaluate::Status;
+
+/// How one assertion fared in each run.
+#[derive(Serialize, Debug)]
+pub struct Flakiness {
+    pub id: String,
+    pub display_type: String,
+    pub message: String,
+    /// The status in each run, in the order the runs were given, `None`
+    /// for runs without the assertion.
+    pub runs: Vec<Option<Status>>,
+    pub passed: usize,
+    /// Runs where it failed, leaving out quarantined and unevaluated results.
+    pub failed: usize,
+    /// Whether it both passed and failed.
+    pub flaky: bool,
+    /// From 0 when every run agreed, to 1 when it passed and failed
+    /// equally often.
+    pub score: f64,
+}
+
+/// The flakiness of every assertion in `runs`, by id.
+pub fn flakiness(runs: Vec<Vec<Outcome>>) -> Vec<Flakiness> {
+    let count = runs.len();
+    let mut by_id: BTreeMap<String, Flakiness> = BTreeMap::new();
+    for (i, run) in runs.into_iter().enumerate() {
+        for outcome in run {
+            let flakiness = by_id.entry(outcome.id.clone()).or_insert_with(|| Flakiness {
+                id: outcome.id.clone(),
+                display_type: outcome.display_type.clone(),
+                message: outcome.message.clone(),
+                runs: vec![None; count],
+                passed: 0,
+                failed: 0,
+                flaky: false,
+                score: 0.0,
+            });
+            flakiness.runs[i] = Some(outcome.status);
+            if outcome.status == Status::Passed {
+                flakiness.passed += 1;
+            } else if outcome.failed() {
+                flakiness.failed += 1;
+            }
+        }
+    }
+    let mut assertions: Vec<Flakiness> = by_id.into_values().collect();
+    for x in &mut assertions {
+        x.flaky = x.passed > 0 && x.failed > 0;
+        let decided = x.passed + x.failed;
+        if decided > 0 {
+            x.score = 1.0 - x.passed.abs_diff(x.failed) as f64 / decided as f64;
+        }
+    }
+    assertions
+}
@@ -0,0 +1,113 @@
+// Aggregating the guidance the SDK writes for numeric and boolean assertions.
+
+use serde::{ Deserialize, Serialize };
+use serde_json::{ Value };
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+
+use crate::sdk::{ AntithesisGuidance, GuidanceType, Location };
+
+/// What was observed for one guidance id over the run.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct GuidanceSummary {
+    pub id: String,
+    pub guidance_type: GuidanceType,
+    pub message: String,
+    pub location: Location,
+    /// Whether the exploration tries to make `left - right` larger rather than smaller.
+    pub maximize: bool,
+    pub hits: usize,
+    /// The smallest `left - right` seen, for numeric guidance.
+    pub min_difference: Option<f64>,
+    /// The largest `left - right` seen, for numeric guidance.
+    pub max_difference: Option<f64>,
+    /// How many hits had each named condition true, for boolean and json
+    /// guidance (whose data is an object of named conditions).
+    pub true_counts: BTreeMap<String, usize>,
+}
+
+/// Collects the `antithesis_guidance` lines of one or more inputs.
+#[derive(Default, Clone)]
+pub struct Guidance {
+    by_id: BTreeMap<String, GuidanceSummary>,
+}
+
+impl Guidance {
+    pub fn record(&mut self, guidance: &AntithesisGuidance) {
+        let summary = self.by_id.entry(guidance.id.clone()).or_insert_with(|| GuidanceSummary {
+            id: guidance.id.clone(),
+            guidance_type: guidance.guidance_type,
+            message: guidance.message.clone(),
+            location: guidance.location.clone(),
+            maximize: guidance.maximize,
+            hits: 0,
+            min_difference: None,
+            max_difference: None,
+            true_counts: BTreeMap::new(),
+        });
+        if !guidance.hit {
+            return;
+        }
+        summary.hits += 1;
+        match guidance.guidance_type {
+            GuidanceType::Numeric => if let Some(difference) = difference(&guidance.guidance_data) {
+                summary.min_difference = Some(summary.min_difference.map_or(difference, |x| x.min(difference)));
+                summary.max_difference = Some(summary.max_difference.map_or(difference, |x| x.max(difference)));
+            },
+            GuidanceType::Boolean | GuidanceType::Json => if let Value::Object(conditions) = &guidance.guidance_data {
+                for (name, value) in conditions {
+                    let count = summary.true_counts.entry(name.clone()).or_default();
+                    if truthy(value) {
+                        *count += 1;
+                    }
+                }
+            },
+        }
+    }
+
+    /// Add what `other` collected, e.g. from another input.
+    pub fn merge(&mut self, other: Guidance) {
+        for (id, theirs) in other.by_id {
+            let summary = match self.by_id.entry(id) {
+                Entry::Vacant(x) => {
+                    x.insert(theirs);
+                    continue;
+                },
+                Entry::Occupied(x) => x.into_mut(),
+            };
+            summary.hits += theirs.hits;
+            summary.min_difference = match (summary.min_difference, theirs.min_difference) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            summary.max_difference = match (summary.max_difference, theirs.max_difference) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            for (name, count) in theirs.true_counts {
+                *summary.true_counts.entry(name).or_default() += count;
+            }
+        }
+    }
+
+    /// The guidance by id.
+    pub fn finish(self) -> Vec<GuidanceSummary> {
+        self.by_id.into_values().collect()
+    }
+}
+
+// Booleans as they are, json conditions as JavaScript would see them
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(x) => *x,
+        Value::Number(x) => x.as_f64() != Some(0.0),
+        Value::String(x) => !x.is_empty(),
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+// `left - right` of numeric guidance data
+fn difference(data: &Value) -> Option<f64> {
+    Some(data.get("left")?.as_f64()? - data.get("right")?.as_f64()?)
+}
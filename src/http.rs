@@ -0,0 +1,173 @@
+// Just enough HTTP/1.1 for `crunch serve`: one request per connection,
+// answered and then closed.
+
+use anyhow::{ Context, Result, bail };
+use std::io::{ BufRead, Write };
+use std::time::Duration;
+
+// Bodies larger than this are refused rather than read into memory
+const MAX_BODY_BYTES: usize = 256 * 1024 * 1024;
+
+/// How long to wait for a client to send more of its request, so that idle
+/// connections don't tie up the server.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A request read by [`read_request`].
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// The path without the query, e.g. `/lines`.
+    pub path: String,
+    /// What followed the `?`, if anything.
+    pub query: String,
+    /// With lowercase names.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The value of the header `name` (lowercase), if it was sent.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(x, _)| x == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read one request, with its body when it has a `Content-Length` or is
+/// sent in chunks.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let start = read_line(reader)?;
+    let mut parts = start.split(' ');
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("malformed request line {:?}", start);
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').with_context(|| format!("malformed header {:?}", line))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+    let mut request = Request { method: method.to_string(), path: path.to_string(), query: query.to_string(), headers, body: Vec::new() };
+
+    if request.header("transfer-encoding").is_some_and(|x| x.eq_ignore_ascii_case("chunked")) {
+        request.body = read_chunked(reader)?;
+    } else if let Some(len) = request.header("content-length") {
+        let len: usize = len.parse().with_context(|| format!("invalid Content-Length {:?}", len))?;
+        if len > MAX_BODY_BYTES {
+            bail!("request body of {} bytes is too large", len);
+        }
+        request.body = vec![0; len];
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(request)
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        bail!("connection closed in the middle of a request");
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).with_context(|| format!("invalid chunk size {:?}", size))?;
+        if size == 0 {
+            // Skip any trailers up to the blank line
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+        let start = body.len();
+        let end = match start.checked_add(size) {
+            Some(end) if end <= MAX_BODY_BYTES => end,
+            _ => bail!("request body is too large"),
+        };
+        body.resize(end, 0);
+        reader.read_exact(&mut body[start..])?;
+        read_line(reader)?;
+    }
+}
+
+/// Write a complete response, after which the connection is closed.
+pub fn respond(out: &mut impl Write, status: u16, content_type: &str, body: &[u8]) -> Result<()> {
+    write!(out, "HTTP/1.1 {} {}\r\n", status, reason(status))?;
+    write!(out, "Content-Type: {}\r\n", content_type)?;
+    write!(out, "Content-Length: {}\r\n", body.len())?;
+    write!(out, "Connection: close\r\n\r\n")?;
+    out.write_all(body)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Start a stream of Server-Sent Events, which stays open for
+/// [`send_event`] until the client goes away.
+pub fn start_events(out: &mut impl Write) -> Result<()> {
+    write!(out, "HTTP/1.1 200 OK\r\n")?;
+    write!(out, "Content-Type: text/event-stream\r\n")?;
+    write!(out, "Cache-Control: no-cache\r\n\r\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Send one event named `event`, whose `data` may span several lines.
+pub fn send_event(out: &mut impl Write, event: &str, data: &str) -> Result<()> {
+    writeln!(out, "event: {}", event)?;
+    for line in data.lines() {
+        writeln!(out, "data: {}", line)?;
+    }
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Send a comment, which the client ignores, to find out whether it is
+/// still there.
+pub fn keep_alive(out: &mut impl Write) -> Result<()> {
+    write!(out, ":\n\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(request: &str) -> Result<Request> {
+        read_request(&mut Cursor::new(request.as_bytes()))
+    }
+
+    #[test]
+    fn chunked_body() {
+        let request = read("POST /lines HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nabcd\r\n3;x=y\r\nefg\r\n0\r\nTrailer: z\r\n\r\n").unwrap();
+        assert_eq!(request.body, b"abcdefg");
+    }
+
+    #[test]
+    fn oversized_chunks_are_refused() {
+        let error = read("POST /lines HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nabcd\r\nffffffffffffffff\r\n").unwrap_err();
+        assert_eq!(error.to_string(), "request body is too large");
+        let error = read(&format!("POST /lines HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n", MAX_BODY_BYTES + 1)).unwrap_err();
+        assert_eq!(error.to_string(), "request body is too large");
+        assert!(read(&format!("POST /lines HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1)).is_err());
+    }
+}
@@ -0,0 +1,418 @@
+// Opening the sources that SDK output is read from.
+
+use anyhow::{ Context, Result, bail };
+use flate2::bufread::MultiGzDecoder;
+use glob::Pattern;
+use memmap2::Mmap;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{ self, BufRead, BufReader, Read };
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+/// The path that stands for standard input.
+pub const STDIN: &str = "-";
+
+/// Prefix of an input that is a Unix domain socket to connect to,
+/// e.g. `unix:/tmp/sdk.sock`.
+pub const UNIX_SOCKET_PREFIX: &str = "unix:";
+
+/// File extensions picked up from directories when no include pattern is given.
+pub const EXTENSIONS: &[&str] = &["json", "jsonl"];
+
+/// Extensions of compressed files, which are allowed after one of [`EXTENSIONS`].
+pub const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "zst"];
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Open `path` for reading, where `-` means standard input and
+/// `unix:PATH` connects to a Unix domain socket.
+///
+/// A named pipe is read until its last writer closes it, and a socket until
+/// the other end closes it. gzip and zstd compressed input is recognized by
+/// its magic bytes and decompressed on the fly.
+pub fn open(path: &str) -> Result<Box<dyn BufRead>> {
+    let reader: Box<dyn BufRead> = if path == STDIN {
+        Box::new(io::stdin().lock())
+    } else if let Some(socket) = path.strip_prefix(UNIX_SOCKET_PREFIX) {
+        Box::new(BufReader::new(connect(Path::new(socket))?))
+    } else {
+        let file = fs::File::open(path)
+            .with_context(|| format!("unable to read {}", path))?;
+        Box::new(BufReader::new(file))
+    };
+    decompress(reader).with_context(|| format!("unable to read {}", path))
+}
+
+/// Like [`open`], but a regular file is mapped into memory instead of read,
+/// so that [`parse_lines`](crate::parse_lines) can parse its lines where
+/// they are (see [`ParsedLines::parallel`](crate::ParsedLines::parallel)).
+///
+/// The file must not be truncated while it is mapped, which would crash the
+/// process. Other inputs are opened as usual.
+pub fn open_mapped(path: &str) -> Result<Box<dyn BufRead>> {
+    if path == STDIN || path.starts_with(UNIX_SOCKET_PREFIX) {
+        return open(path);
+    }
+    let file = fs::File::open(path)
+        .with_context(|| format!("unable to read {}", path))?;
+    if !file.metadata()?.is_file() {
+        return open(path);
+    }
+    // SAFETY: the map is only read, and the caller keeps the file from
+    // being truncated while it is mapped
+    let map = unsafe { Mmap::map(&file) }
+        .with_context(|| format!("unable to map {}", path))?;
+    decompress(Box::new(Mapped { map, pos: 0 })).with_context(|| format!("unable to read {}", path))
+}
+
+// A mapped file, read as if it was all in the buffer of a BufReader
+struct Mapped {
+    map: Mmap,
+    pos: usize,
+}
+
+impl Read for Mapped {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = (&self.map[self.pos..]).read(out)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl BufRead for Mapped {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.map[self.pos..])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.pos = (self.pos + n).min(self.map.len());
+    }
+}
+
+/// Open `path` to be read with [`Follow`], as it is written.
+///
+/// A file or named pipe is kept open, so a pipe picks up from the next
+/// writer once the last one closed it. A `unix:PATH` socket is connected to
+/// again whenever the other end closes it, see [`Reconnect`].
+pub fn follow(path: &str) -> Result<Box<dyn Read>> {
+    if let Some(socket) = path.strip_prefix(UNIX_SOCKET_PREFIX) {
+        return reconnect(Path::new(socket));
+    }
+    let file = fs::File::open(path)
+        .with_context(|| format!("unable to read {}", path))?;
+    Ok(Box::new(file))
+}
+
+#[cfg(unix)]
+fn connect(path: &Path) -> Result<UnixStream> {
+    UnixStream::connect(path).with_context(|| format!("unable to connect to {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn connect(path: &Path) -> Result<io::Empty> {
+    bail!("unable to connect to {}: Unix domain sockets are not supported on this platform", path.display())
+}
+
+/// Reads a Unix domain socket without blocking, connecting to it again
+/// after the other end closed it.
+///
+/// Having nothing to read, whether connected or not, looks like the end of
+/// the input, as [`Follow`] expects. A closed connection ends with a newline,
+/// so that a line it cut short is not joined with the first line of the next.
+#[cfg(unix)]
+pub struct Reconnect {
+    path: PathBuf,
+    stream: Option<UnixStream>,
+}
+
+#[cfg(unix)]
+impl Reconnect {
+    /// Connect to the socket at `path`, which must be listening already.
+    pub fn new(path: &Path) -> Result<Self> {
+        let stream = connect(path)?;
+        stream.set_nonblocking(true)?;
+        Ok(Reconnect { path: path.to_path_buf(), stream: Some(stream) })
+    }
+}
+
+#[cfg(unix)]
+impl Read for Reconnect {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => match UnixStream::connect(&self.path) {
+                Ok(stream) => {
+                    stream.set_nonblocking(true)?;
+                    self.stream.insert(stream)
+                },
+                // Not listening again yet
+                Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused) => return Ok(0),
+                Err(e) => return Err(e),
+            },
+        };
+        match stream.read(out) {
+            Ok(0) if !out.is_empty() => {
+                self.stream = None;
+                out[0] = b'\n';
+                Ok(1)
+            },
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
+            x => x,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn reconnect(path: &Path) -> Result<Box<dyn Read>> {
+    Ok(Box::new(Reconnect::new(path)?))
+}
+
+#[cfg(not(unix))]
+fn reconnect(path: &Path) -> Result<Box<dyn Read>> {
+    Ok(Box::new(connect(path)?))
+}
+
+fn decompress(mut reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>> {
+    let head = reader.fill_buf()?;
+    if head.starts_with(GZIP_MAGIC) {
+        // Multi so that concatenated .gz files are read to the end
+        return Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))));
+    }
+    if head.starts_with(ZSTD_MAGIC) {
+        return Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)));
+    }
+    Ok(reader)
+}
+
+// How much is read from a followed file at a time
+const FOLLOW_CHUNK: usize = 64 * 1024;
+
+/// Reads a file that is still being written, one complete line at a time.
+///
+/// A line is only handed out once its newline has been written, until then
+/// the reader is at its end. Reading again later picks up whatever was
+/// appended in the meantime, so a [`ParsedLines`](crate::ParsedLines) over
+/// it can be resumed after it returned `None`.
+pub struct Follow<R> {
+    inner: R,
+    buf: Vec<u8>,
+    // Start of what was not consumed yet
+    pos: usize,
+    // End of the last complete line in buf
+    complete: usize,
+}
+
+impl<R: Read> Follow<R> {
+    pub fn new(inner: R) -> Self {
+        Follow { inner, buf: Vec::new(), pos: 0, complete: 0 }
+    }
+}
+
+impl<R: Read> Read for Follow<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for Follow<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.complete {
+            // Keep the partial line at the end and read more of it
+            self.buf.drain(..self.complete);
+            self.pos = 0;
+            self.complete = 0;
+            let mut chunk = [0; FOLLOW_CHUNK];
+            loop {
+                let n = match self.inner.read(&mut chunk) {
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                if n == 0 {
+                    break;
+                }
+                self.buf.extend_from_slice(&chunk[..n]);
+                if let Some(end) = self.buf.iter().rposition(|x| *x == b'\n') {
+                    self.complete = end + 1;
+                    break;
+                }
+            }
+        }
+        Ok(&self.buf[self.pos..self.complete])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.pos = (self.pos + n).min(self.complete);
+    }
+}
+
+/// The first two non-blank lines of `reader`, enough to tell a report from
+/// SDK output with [`is_report_start`](crate::is_report_start).
+pub fn read_head(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut head = String::new();
+    let mut lines = 0;
+    while lines < 2 {
+        let start = head.len();
+        if reader.read_line(&mut head)? == 0 {
+            break;
+        }
+        if !head[start..].trim().is_empty() {
+            lines += 1;
+        }
+    }
+    Ok(head)
+}
+
+/// Whether `path` is SDK output to read rather than a file that may be
+/// written: a directory, a glob pattern, or an existing file with one of
+/// [`EXTENSIONS`] that does not hold a crunch report.
+pub fn is_sdk_input(path: &str) -> bool {
+    if path == STDIN {
+        return false;
+    }
+    if path.starts_with(UNIX_SOCKET_PREFIX) || path.contains(['*', '?', '[']) {
+        return true;
+    }
+    let file = Path::new(path);
+    if file.is_dir() {
+        return true;
+    }
+    if !file.is_file() || !has_input_extension(file) {
+        return false;
+    }
+    // An earlier report is overwritten by the legacy `crunch <input> <output>`
+    match open(path).and_then(|mut x| Ok(read_head(&mut x)?)) {
+        Ok(head) => !crate::is_report_start(&head),
+        Err(_) => true,
+    }
+}
+
+// e.g. "run.jsonl" or "run.jsonl.gz"
+fn has_input_extension(path: &Path) -> bool {
+    let path = match path.extension() {
+        Some(ext) if COMPRESSED_EXTENSIONS.iter().any(|x| ext == *x) => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+    path.extension().is_some_and(|x| EXTENSIONS.iter().any(|ext| x == *ext))
+}
+
+/// How directories and glob patterns are turned into input files.
+#[derive(Default, Debug)]
+pub struct Discover {
+    /// Descend into subdirectories of directory inputs.
+    pub recursive: bool,
+    /// Only pick up files whose name matches one of these, instead of
+    /// [`EXTENSIONS`] (optionally followed by one of [`COMPRESSED_EXTENSIONS`]).
+    pub include: Vec<Pattern>,
+    /// Skip files whose name matches any of these.
+    pub exclude: Vec<Pattern>,
+}
+
+impl Discover {
+    fn excluded(&self, path: &Path) -> bool {
+        let name = path.file_name().map(|x| x.to_string_lossy()).unwrap_or_default();
+        self.exclude.iter().any(|x| x.matches(&name))
+    }
+
+    fn wanted(&self, path: &Path) -> bool {
+        let name = path.file_name().map(|x| x.to_string_lossy()).unwrap_or_default();
+        let included = if self.include.is_empty() {
+            has_input_extension(path)
+        } else {
+            self.include.iter().any(|x| x.matches(&name))
+        };
+        included && !self.excluded(path)
+    }
+
+    fn walk(&self, dir: &Path, found: &mut Vec<String>) -> Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("unable to read directory {}", dir.display()))?
+            .map(|x| x.map(|x| x.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                if self.recursive {
+                    self.walk(&path, found)?;
+                }
+            } else if self.wanted(&path) {
+                found.push(path.to_string_lossy().into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Expand each of `paths` into the files to read.
+    ///
+    /// Plain files (and `-`) are kept as given, directories are searched for
+    /// matching files and glob patterns such as `logs/**/*.jsonl` are expanded.
+    /// Files found more than once are only returned the first time.
+    pub fn expand(&self, paths: &[String]) -> Result<Vec<String>> {
+        let mut found = Vec::new();
+        for path in paths {
+            if path == STDIN || Path::new(path).is_file() {
+                found.push(path.clone());
+            } else if Path::new(path).is_dir() {
+                self.walk(Path::new(path), &mut found)?;
+            } else if path.contains(['*', '?', '[']) {
+                let before = found.len();
+                for entry in glob::glob(path).with_context(|| format!("invalid pattern {}", path))? {
+                    let entry = entry?;
+                    if entry.is_dir() {
+                        self.walk(&entry, &mut found)?;
+                    } else if !self.excluded(&entry) {
+                        found.push(entry.to_string_lossy().into_owned());
+                    }
+                }
+                if found.len() == before {
+                    bail!("no input files match {}", path);
+                }
+            } else {
+                // Let open() report the missing file
+                found.push(path.clone());
+            }
+        }
+
+        // A file can be reached through several paths or patterns, read it once
+        let mut seen = HashSet::new();
+        found.retain(|x| x == STDIN || seen.insert(x.clone()));
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_path_is_told_from_sdk_output() {
+        let dir = std::env::temp_dir().join(format!("crunch-input-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let sdk_output = r#"{"antithesis_assert": {"id": "a", "message": "a", "assert_type": "always", "display_type": "Always", "condition": true, "hit": true, "must_hit": true, "location": {"file": "src/a.rs"}, "details": null}}"#;
+        fs::write(path("run.jsonl"), sdk_output).unwrap();
+        fs::write(path("report.json"), "{\n  \"schema_version\": 1,\n  \"assertions\": []\n}\n").unwrap();
+        fs::write(path("report.xml"), "<testsuites/>\n").unwrap();
+
+        assert!(is_sdk_input(&path("run.jsonl")));
+        assert!(is_sdk_input(&dir.to_string_lossy()));
+        assert!(is_sdk_input(&path("*.jsonl")));
+        assert!(is_sdk_input("unix:/tmp/sdk.sock"));
+        // What the legacy `crunch <input> <output>` writes, or may overwrite
+        assert!(!is_sdk_input(&path("report.json")));
+        assert!(!is_sdk_input(&path("report.xml")));
+        assert!(!is_sdk_input(&path("new.jsonl")));
+        assert!(!is_sdk_input(STDIN));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+//! Evaluate the JSON lines emitted by the Antithesis SDKs.
+//!
+//! The SDK output is read line-by-line and parsed into [`SDKInput`] values.
+//! Everything that is not an [`AntithesisAssert`] is filtered out and the
+//! assertions are grouped by their `id` (see [`group_asserts`]).
+//!
+//! Each group is then evaluated into an [`EvaluatedAssertion`]:
+//! - is there an entry with hit==true && condition==true, or hit==true && condition==false?
+//! - given the assert_type and must_hit of the catalog entry (hit==false),
+//!   was the assertion passed or failed?
+//!
+//! ```no_run
+//! let reader = crunch::input::open("sdk.jsonl")?;
+//! let parsed = crunch::parse_lines(reader).collect::<anyhow::Result<Vec<_>>>()?;
+//! for (_id, asserts) in crunch::group_asserts(parsed) {
+//!     let evaled = crunch::EvaluatedAssertion::new(asserts);
+//!     println!("{} passed={}", evaled.id, evaled.passed);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+mod details;
+mod diff;
+mod evaluate;
+mod events;
+mod filter;
+mod flaky;
+mod guidance;
+pub mod http;
+pub mod input;
+pub mod lint;
+pub mod output;
+mod parse;
+mod quarantine;
+mod report;
+pub mod schema;
+mod sdk;
+mod severity;
+mod timestamp;
+
+pub use details::{ redact_details, truncate_details };
+pub use diff::{ Change, Diff, Outcome };
+pub use evaluate::{ group_asserts, group_asserts_with_capacity, merge_evaluated, merge_groups, CatalogConflict, EvaluatedAssertion, FailureReason, FirstFailure, Groups, PassReason, Status };
+pub use events::{ ContextEvent, EventSummary, Events, Timeline, TimelineEvent };
+pub use filter::{ EventFilter, Filter };
+pub use flaky::{ flakiness, Flakiness };
+pub use guidance::{ Guidance, GuidanceSummary };
+pub use parse::{ parse_line, parse_line_strict, parse_lines, ParseError, ParsedLines };
+pub use quarantine::Quarantine;
+pub use report::{ is_report_line, is_report_start, read_assertions, FileSummary, GitInfo, GroupBy, Report, RunInfo, SortKey, Summary, TypeSummary, SCHEMA_VERSION };
+pub use sdk::{ AntithesisAssert, AntithesisGuidance, AntithesisSdk, AntithesisSetup, AssertType, GuidanceType, Location, SDKInput };
+pub use severity::{ Severities, Severity };
+pub use timestamp::{ Span, Timestamp };
@@ -0,0 +1,114 @@
+// Structural problems in the SDK output that don't stop it from being evaluated.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::sdk::{ AntithesisAssert, Location };
+
+/// Where a line came from, e.g. for pointing at it in a problem.
+#[derive(Clone, Debug)]
+pub struct Source {
+    pub file: String,
+    pub line: usize,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Hits for an id that has no catalog entry.
+    NeverRegistered,
+    /// A catalog entry without any hits, e.g. dead instrumentation.
+    NeverExercised,
+    /// Catalog entries for one id in different places.
+    ConflictingLocations,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::NeverRegistered => "never_registered",
+            Kind::NeverExercised => "never_exercised",
+            Kind::ConflictingLocations => "conflicting_locations",
+        }
+    }
+
+    /// Whether the problem makes the input invalid rather than only suspect.
+    pub fn is_error(self) -> bool {
+        self == Kind::ConflictingLocations
+    }
+}
+
+#[derive(Debug)]
+pub struct Problem {
+    pub kind: Kind,
+    pub id: String,
+    /// The line the problem was found at.
+    pub source: Source,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Seen {
+    catalog: Vec<(Source, Location)>,
+    first_hit: Option<Source>,
+}
+
+/// Collects the assertions of one or more inputs, then reports the problems
+/// found across all of them.
+#[derive(Default)]
+pub struct Linter {
+    by_id: BTreeMap<String, Seen>,
+}
+
+impl Linter {
+    pub fn record(&mut self, source: Source, assert: &AntithesisAssert) {
+        let seen = self.by_id.entry(assert.group_id().into_owned()).or_default();
+        if assert.hit {
+            seen.first_hit.get_or_insert(source);
+        } else {
+            seen.catalog.push((source, assert.location.clone()));
+        }
+    }
+
+    /// The problems by id, with the problems of each id in the order they were read.
+    pub fn finish(self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (id, seen) in self.by_id {
+            let Some((first, location)) = seen.catalog.first() else {
+                let source = seen.first_hit.expect("an id is only seen through an assertion");
+                problems.push(Problem {
+                    kind: Kind::NeverRegistered,
+                    message: format!("assertion {:?} was hit but has no catalog entry", id),
+                    id,
+                    source,
+                });
+                continue;
+            };
+            for (source, other) in &seen.catalog[1..] {
+                if other != location {
+                    problems.push(Problem {
+                        kind: Kind::ConflictingLocations,
+                        id: id.clone(),
+                        source: source.clone(),
+                        message: format!("assertion {:?} is registered at {} but was already registered at {} ({})",
+                            id, other, location, first),
+                    });
+                }
+            }
+            if seen.first_hit.is_none() {
+                problems.push(Problem {
+                    kind: Kind::NeverExercised,
+                    message: format!("assertion {:?} is registered but was never hit", id),
+                    id,
+                    source: first.clone(),
+                });
+            }
+        }
+        problems
+    }
+}
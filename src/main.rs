@@ -1,240 +1,1174 @@
-// Get input file and output filename from cmd line! (positional args)
-// Open and read line-by-line
-// Parse each line into a struct
-// filtering out anything that is not AntithesisAssert{}
-// and inserts into a map<id, Vec<struct>
-//
-// Now with each key in map
-// - do we have an item in the vec with hit==true && cond==true => passed:= true;  hit==false;
-// - determine if each assertion was passed or failed
-// Output each item with pass/fail indication (and other info) to JSON output file
-//
-
+use anyhow::{ bail, Context, Result };
+use clap::Parser;
+use notify::{ RecursiveMode, Watcher };
+use rayon::prelude::*;
+use serde_json::{ json, Value };
+use std::collections::{ BTreeMap, HashMap, HashSet };
 use std::env;
 use std::fs;
-use serde::{ Deserialize, Serialize };
-use serde_json::{ Value };
-use anyhow::{ Result, bail };
-use std::collections::HashMap;
-use std::io::Write;
-
-#[derive(Deserialize, Debug)]
-#[allow(dead_code)]
-struct AntithesisSdk {
-    language: String, 
-    version: String 
-}
-
-#[derive(Deserialize, Debug)]
-#[allow(dead_code)]
-struct AntithesisSetup {
-    status: String,
-    details: Value,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-struct Location {
-    begin_column: i32,
-    begin_line: i32,
-    class: String,
-    file: String,
-    function: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct AntithesisAssert {
-    assert_type: AssertType,
-    condition: bool,
-    display_type: String,
-    hit: bool,
-    must_hit: bool,
-    id: String,
-    message: String,
-    location: Location,
-    details: Value,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
-enum SDKInput {
-    AntithesisSdk(AntithesisSdk),
-    AntithesisAssert(AntithesisAssert),
-    AntithesisSetup(AntithesisSetup),
-
-    #[allow(dead_code)]
-    SendEvent{event_name: String, details: Value }
-}
-
-#[derive(Serialize, Debug)]
-struct EvaluatedAssertion {
-    display_type: String,
-    id: String,
-    message: String,
-    location: Location,
-    example_details: Option<Value>,
-    counter_details: Option<Value>,
-    passed: bool,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
-enum AssertType {
-    Always,
-    Sometimes,
-    Reachability,
-}
-
-impl EvaluatedAssertion {
-    fn new(assert_list: Vec<AntithesisAssert>) -> Self {
-
-        let mut catalog_entry = None;
-        let mut condition_true_entry = None;
-        let mut condition_false_entry = None;
-
-        for entry in assert_list {
-            let hit = entry.hit;
-            if hit {
-                let condition = entry.condition;
-                if condition {
-                    condition_true_entry = Some(entry);
-                } else {
-                    condition_false_entry = Some(entry);
-                }
-            } else {
-                catalog_entry = Some(entry);
+use std::io::{ self, BufRead, BufReader, IsTerminal, Read, Write };
+use std::iter;
+use std::net::{ TcpListener, TcpStream };
+use std::path::{ Path, PathBuf };
+use std::process::ExitCode;
+use std::sync::{ Arc, Mutex };
+use std::sync::mpsc::{ self, RecvTimeoutError };
+use std::thread;
+use std::time::{ Duration, Instant, SystemTime };
+
+use crunch::http::{ self, Request };
+use crunch::input::Follow;
+use crunch::lint::{ Kind, Linter, Source };
+use crunch::output::parquet::ParquetHits;
+use crunch::output::sqlite::SqliteExport;
+use crunch::schema::InputSchema;
+use crunch::{ group_asserts, group_asserts_with_capacity, merge_evaluated, merge_groups, output, parse_lines, redact_details, truncate_details, Diff, EvaluatedAssertion, EventFilter, Events, Filter, GitInfo, Groups, Guidance, Outcome, ParseError, Quarantine, Report, RunInfo, SDKInput, Severities, Severity, Span, Status, Timeline, TimelineEvent };
+
+mod cli;
+mod config;
+mod tui;
+
+use cli::{ Cli, Command, DiffArgs, TextFormat, EvaluateArgs, EventFormat, EventsArgs, ExportArgs, FailOn, FlakyArgs, Format, HistoryArgs, InputArgs, ListenArgs, MergeArgs, SchemaArgs, SchemaDocument, ServeArgs, SummaryArgs, TailArgs, TuiArgs, ValidateArgs, WatchArgs };
+use config::Config;
+
+// Exit status when --fail-on matched, distinct from the 1 used for errors
+const EXIT_FAILED: u8 = 2;
+const EXIT_UNHIT: u8 = 3;
+
+const GITHUB_STEP_SUMMARY: &str = "GITHUB_STEP_SUMMARY";
+
+// Logs have far more hits than assertions, a guess at how much input there
+// is per assertion, erring on more assertions
+const INPUT_BYTES_PER_ID: u64 = 64 * 1024;
+const MAX_GUESSED_IDS: u64 = 64 * 1024;
+
+fn main() -> Result<ExitCode>{
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or_else(|| Command::Evaluate(Box::new(cli.evaluate)));
+    let config = Config::load(cli.config.as_deref())?;
+    match command {
+        Command::Evaluate(args) => evaluate(*args, config),
+        Command::Summary(args) => summary(args, config).map(|_| ExitCode::SUCCESS),
+        Command::Validate(args) => validate(args),
+        Command::Export(args) => export(args, config).map(|_| ExitCode::SUCCESS),
+        Command::Schema(args) => schema(args).map(|_| ExitCode::SUCCESS),
+        Command::Events(args) => events(args).map(|_| ExitCode::SUCCESS),
+        Command::Diff(args) => diff(args),
+        Command::Merge(args) => merge(args),
+        Command::Flaky(args) => flaky(args).map(|_| ExitCode::SUCCESS),
+        Command::History(args) => history(args).map(|_| ExitCode::SUCCESS),
+        Command::Tail(args) => tail(args, config).map(|_| ExitCode::SUCCESS),
+        Command::Watch(args) => watch(args, config).map(|_| ExitCode::SUCCESS),
+        Command::Listen(args) => listen(args, config).map(|_| ExitCode::SUCCESS),
+        Command::Serve(args) => serve(args, config).map(|_| ExitCode::SUCCESS),
+        Command::Tui(args) => browse(args).map(|_| ExitCode::SUCCESS),
+    }
+}
+
+fn exit_code(fail_on: FailOn, evaled_assertions: &[EvaluatedAssertion]) -> ExitCode {
+    let failed = |x: &&EvaluatedAssertion| !x.passed && !x.expected_failure && !x.unevaluated && x.severity == Severity::Error;
+    let any_failed = evaled_assertions.iter().any(|x| failed(&x));
+    let any_reached_failed = evaled_assertions.iter().filter(failed).any(|x| !matches!(x.status, Status::NotHit | Status::NotReached));
+    let any_unhit = evaled_assertions.iter().any(|x| !x.hit);
+    match fail_on {
+        FailOn::Failed | FailOn::AnyUnhit if any_failed => ExitCode::from(EXIT_FAILED),
+        FailOn::Reached if any_reached_failed => ExitCode::from(EXIT_FAILED),
+        FailOn::AnyUnhit if any_unhit => ExitCode::from(EXIT_UNHIT),
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+// Called with where each line was read and what was parsed from it, before grouping
+type Observer<'a> = &'a mut dyn FnMut(&Source, &SDKInput) -> Result<()>;
+
+fn group_input(input_file: &str, args: &InputArgs, context_events: usize, events: &EventFilter, filter: &Filter, parse_errors: &mut Vec<ParseError>, observe: Observer) -> Result<(Groups, Span)> {
+    let strict = args.strict;
+    let reader = args.open(input_file)?;
+
+    // Group while parsing so only the assertions are kept in memory.
+    // Lines that cannot be parsed are set aside unless strict,
+    // but a read error always stops the run
+    let mut skipped = 0;
+    let mut error = None;
+    let mut lines = parse_lines(reader)
+        .parallel(true)
+        .strict(strict)
+        .context_events(context_events)
+        .events(events.clone())
+        .timestamp_field(args.timestamp_field.clone())
+        .namespace(args.namespace_by_file.then(|| input_file.to_string()));
+    let parsed = iter::from_fn(|| {
+        while let Some(x) = lines.next() {
+            match x {
+                // Filtered before it is observed, so that what is exported
+                // next to the assertions covers the same ones
+                Ok(x) if !filter.keeps(&x) => {},
+                Ok(x) => {
+                    let source = Source { file: input_file.to_string(), line: lines.line() };
+                    if let Err(e) = observe(&source, &x) {
+                        error = Some(e);
+                        return None;
+                    }
+                    return Some(x);
+                },
+                Err(e) if !strict && e.is::<ParseError>() => {
+                    let mut e: ParseError = e.downcast().unwrap();
+                    e.file = Some(input_file.to_string());
+                    parse_errors.push(e);
+                    skipped += 1;
+                },
+                Err(e) => {
+                    error = Some(e);
+                    return None;
+                },
+            }
+        }
+        None
+    });
+    // Room for the ids up front, guessed from the size of the input
+    let capacity = fs::metadata(input_file).map_or(0, |x| (x.len() / INPUT_BYTES_PER_ID).min(MAX_GUESSED_IDS) as usize);
+    let grouped_assertions = group_asserts_with_capacity(parsed, capacity);
+    if let Some(e) = error {
+        return Err(e.context(format!("unable to parse {}", input_file)));
+    }
+    if skipped > 0 {
+        eprintln!("SKIPPED: {} unparseable lines in {}", skipped, input_file);
+    }
+    Ok((grouped_assertions, lines.span().clone()))
+}
+
+// The evaluated assertions of all inputs, with what was observed in each
+type Evaluated<T> = (Vec<EvaluatedAssertion>, Vec<ParseError>, Span, Vec<T>);
+
+// Each input file is read on its own thread, with its own `T` for `observe`
+// to collect into. These are returned in the order of the files, like the
+// assertions are merged, so the result doesn't depend on which file was
+// done first
+fn evaluate_input<T: Default + Send>(args: &InputArgs, filter: &Filter, keep_examples: usize, context_events: usize, events: &EventFilter, observe: impl Fn(&mut T, &Source, &SDKInput) -> Result<()> + Sync) -> Result<Evaluated<T>> {
+    let read: Vec<_> = args.files()?.par_iter().map(|input_file| {
+        let mut parse_errors = Vec::new();
+        let mut observed = T::default();
+        let (grouped, span) = group_input(input_file, args, context_events, events, filter, &mut parse_errors, &mut |source, x| observe(&mut observed, source, x))?;
+        Ok((grouped, parse_errors, span, observed))
+    }).collect::<Result<_>>()?;
+
+    let mut parse_errors = Vec::new();
+    let mut grouped_assertions = Groups::default();
+    let mut span = Span::default();
+    let mut observed = Vec::new();
+    for (grouped, mut file_parse_errors, file_span, file_observed) in read {
+        merge_groups(&mut grouped_assertions, grouped);
+        parse_errors.append(&mut file_parse_errors);
+        span.merge(file_span);
+        observed.push(file_observed);
+    }
+
+    // After into_values() the map is no longer useable
+    let evaled_assertions: Vec<EvaluatedAssertion> = grouped_assertions.into_values().map(|x| EvaluatedAssertion::with_examples(x, keep_examples)).collect();
+    for evaled in &evaled_assertions {
+        for conflict in &evaled.catalog_conflicts {
+            eprintln!("WARNING: assertion {:?} is registered as {} {:?} at {} and as {} {:?} at {}", evaled.id,
+                evaled.assert_type.as_str(), evaled.message, evaled.location,
+                conflict.assert_type.as_str(), conflict.message, conflict.location);
+        }
+    }
+    Ok((evaled_assertions, parse_errors, span, observed))
+}
+
+fn evaluate(mut args: EvaluateArgs, mut config: Config) -> Result<ExitCode> {
+    config.apply(&mut args)?;
+    let output = args.take_output();
+    let (mut evaled_assertions, parse_errors, span, observed) = evaluate_input(&args.input, &args.filter.filter(), args.keep_examples, args.context_events, &args.events.filter(), |observed: &mut Observed, source, x| {
+        observed.run_info.record(x);
+        observed.events.record(x);
+        if args.events_timeline {
+            observed.timeline.record(source, x);
+        }
+        record_guidance(&mut observed.guidance, x);
+        Ok(())
+    })?;
+    let mut guidance = Guidance::default();
+    let mut run_info = RunInfo::default();
+    let mut events = Events::default();
+    let mut timeline = args.events_timeline.then(Timeline::default);
+    for x in observed {
+        guidance.merge(x.guidance);
+        run_info.merge(x.run_info);
+        events.merge(x.events);
+        if let Some(timeline) = &mut timeline {
+            timeline.merge(x.timeline);
+        }
+    }
+    classify(&mut evaled_assertions, args.xfail.as_deref(), args.severity.as_deref(), &mut config)?;
+    // Redact first so a truncated preview can't show what was redacted
+    if !args.redact.is_empty() {
+        redact_details(&mut evaled_assertions, &args.redact);
+    }
+    if let Some(max_bytes) = args.max_details_bytes {
+        truncate_details(&mut evaled_assertions, max_bytes);
+    }
+    let mut report = Report::new(evaled_assertions, parse_errors);
+    report.set_guidance(guidance.finish());
+    report.set_span(span);
+    run_info.run_id = args.run_id.clone();
+    run_info.labels = args.labels.iter().cloned().collect();
+    if !args.no_git {
+        run_info.git = GitInfo::detect();
+    }
+    if args.repo_url.is_some() && args.rev.is_none() {
+        args.rev = run_info.git.as_ref().map(|x| x.sha.clone());
+        if args.rev.is_none() {
+            eprintln!("WARNING: --repo-url given without --rev and no git commit found, linking to HEAD");
+        }
+    }
+    report.run_info = run_info;
+    report.events = events.finish();
+    report.timeline = timeline.map(Timeline::finish).unwrap_or_default();
+    // The exit code and summary always cover every assertion
+    if let Some(history) = &args.history {
+        SqliteExport::create(history, &args.input.inputs)?.finish(&report)?;
+    }
+    let exit_code = match &args.baseline {
+        Some(baseline) => compare_baseline(baseline, args.update_baseline, &report)?,
+        None => exit_code(args.fail_on.unwrap_or(FailOn::None), &report.assertions),
+    };
+    if args.only_failed {
+        report.assertions.retain(|x| !x.passed || (args.with_unhit && !x.hit));
+    }
+    args.sort.apply(&mut report);
+    
+    match args.format {
+        Some(format) if format.writes_directory() => write_directory(format, &report, Path::new(&output))?,
+        _ => {
+            let mut file = output::create(&output)?;
+            write_report(&args, &report, &mut file)?;
+            file.flush()?;
+        },
+    }
+
+    if args.github_summary {
+        write_github_summary(&report, &args.output_options())?;
+    }
+    if args.buildkite_annotate {
+        if let Err(e) = output::buildkite::annotate(&report, &args.output_options()) {
+            eprintln!("WARNING: --buildkite-annotate given but {:#}", e);
+        }
+    }
+    if let Some(path) = &args.vscode_diagnostics {
+        output::replace(path, |out| output::diagnostics::write_vscode(&report, out))?;
+    }
+
+    Ok(exit_code)
+}
+
+// Exit with EXIT_FAILED when something fails that didn't in the baseline,
+// then replace the baseline when asked to
+fn compare_baseline(baseline: &Path, update: bool, report: &Report) -> Result<ExitCode> {
+    let mut exit_code = ExitCode::SUCCESS;
+    if baseline.exists() || !update {
+        let text = fs::read_to_string(baseline)
+            .with_context(|| format!("unable to read {}", baseline.display()))?;
+        let before: Vec<Outcome> = crunch::read_assertions(&text)
+            .with_context(|| format!("unable to read {}", baseline.display()))?;
+        let diff = Diff::new(before, report.assertions.iter().map(Outcome::from).collect());
+        for change in &diff.newly_failing {
+            eprintln!("REGRESSION: {} ({} -> {})", change.id, change.before.as_str(), change.after.as_str());
+        }
+        for outcome in diff.added.iter().filter(|x| x.failed()) {
+            eprintln!("REGRESSION: {} (new, {})", outcome.id, outcome.status.as_str());
+        }
+        if diff.has_regression() {
+            exit_code = ExitCode::from(EXIT_FAILED);
+        }
+    }
+    if update {
+        output::replace(baseline, |out| output::json::write(report, out))?;
+    }
+    Ok(exit_code)
+}
+
+// What evaluate collects from the lines of one input besides the assertions
+#[derive(Default)]
+struct Observed {
+    guidance: Guidance,
+    run_info: RunInfo,
+    events: Events,
+    timeline: Timeline,
+}
+
+fn record_guidance(guidance: &mut Guidance, input: &SDKInput) {
+    if let SDKInput::AntithesisGuidance(x) = input {
+        guidance.record(x);
+    }
+}
+
+// Mark the expected failures and set the severities, the files given on
+// the command line take the place of those in the config
+fn classify(evaled_assertions: &mut [EvaluatedAssertion], xfail: Option<&Path>, severity: Option<&Path>, config: &mut Config) -> Result<()> {
+    let quarantine = match xfail {
+        Some(path) => Some(Quarantine::load(path)?),
+        None => config.xfail.take(),
+    };
+    if let Some(quarantine) = quarantine {
+        for evaled in quarantine.apply(evaled_assertions) {
+            eprintln!("WARNING: quarantined assertion passed: {}", evaled.id);
+        }
+    }
+    match severity {
+        Some(path) => Severities::load(path)?.apply(evaled_assertions),
+        None => config.severity.apply(evaled_assertions),
+    }
+    Ok(())
+}
+
+fn write_report(args: &EvaluateArgs, report: &Report, file: &mut dyn Write) -> Result<()> {
+    if let Some(template) = &args.template {
+        return output::template::write(report, template, args.output_options().repo.as_ref(), file);
+    }
+    write_format(args.format.unwrap_or(Format::Jsonl), report, &args.output_options(), &args.detail_columns, file)
+}
+
+fn write_format(format: Format, report: &Report, options: &output::Options, detail_columns: &[String], file: &mut dyn Write) -> Result<()> {
+    match format {
+        Format::Jsonl => output::jsonl::write(report, file),
+        Format::Json => output::json::write(report, file),
+        Format::Yaml => output::yaml::write(report, file),
+        Format::Junit => output::junit::write(report, file),
+        Format::Tap => output::tap::write(report, file),
+        Format::Markdown => output::markdown::write(report, options, file),
+        Format::Csv => output::csv::write(report, ',', detail_columns, file),
+        Format::Tsv => output::csv::write(report, '\t', detail_columns, file),
+        Format::Sarif => output::sarif::write(report, file),
+        Format::Parquet => output::parquet::write(report, file),
+        Format::Diagnostics => output::diagnostics::write(report, file),
+        Format::Checkstyle => output::checkstyle::write(report, file),
+        Format::Codequality => output::codequality::write(report, file),
+        Format::Teamcity => output::teamcity::write(report, file),
+        Format::Buildkite => output::buildkite::write(report, options, file),
+        Format::Azdo => output::azdo::write(report, file),
+        Format::Text => output::text::write(report, file),
+        Format::Allure | Format::Circleci => bail!("the {} format writes a directory, not a single file", format!("{:?}", format).to_lowercase()),
+    }
+}
+
+fn write_directory(format: Format, report: &Report, dir: &Path) -> Result<()> {
+    match format {
+        Format::Allure => output::allure::write(report, dir),
+        Format::Circleci => output::circleci::write(report, dir),
+        _ => bail!("the {} format writes a single file, not a directory", format!("{:?}", format).to_lowercase()),
+    }
+}
+
+fn write_github_summary(report: &Report, options: &output::Options) -> Result<()> {
+    let Ok(path) = env::var(GITHUB_STEP_SUMMARY) else {
+        eprintln!("WARNING: --github-summary given but ${} is not set", GITHUB_STEP_SUMMARY);
+        return Ok(());
+    };
+    let mut file = output::append(&path)?;
+    output::markdown::write(report, options, &mut file)?;
+    writeln!(file)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn summary(mut args: SummaryArgs, mut config: Config) -> Result<()> {
+    config.filter.apply(&mut args.filter)?;
+    config.apply_input(&mut args.input);
+    let (mut evaled_assertions, parse_errors, span, _) = evaluate_input(&args.input, &args.filter.filter(), 0, 0, &EventFilter::default(), |_: &mut (), _, _| Ok(()))?;
+    classify(&mut evaled_assertions, args.xfail.as_deref(), args.severity.as_deref(), &mut config)?;
+    let mut report = Report::new(evaled_assertions, parse_errors);
+    report.set_span(span);
+    args.sort.apply(&mut report);
+    let mut out = output::create(output::STDOUT)?;
+    output::console::write(&report, &args.output_options(), &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn export(mut args: ExportArgs, config: Config) -> Result<()> {
+    config.filter.apply(&mut args.filter)?;
+    config.apply_input(&mut args.input);
+    // Shared by the threads reading the inputs, so hits and events of
+    // different inputs are inserted interleaved
+    let sqlite = args.sqlite.as_deref()
+        .map(|path| SqliteExport::create(path, &args.input.inputs))
+        .transpose()?
+        .map(Mutex::new);
+
+    let (evaled_assertions, parse_errors, _, observed) = evaluate_input(&args.input, &args.filter.filter(), 0, 0, &EventFilter::default(), |hits: &mut ParquetHits, source, x| {
+        if args.parquet_hits.is_some() {
+            hits.record(&source.file, x);
+        }
+        match &sqlite {
+            Some(sqlite) => sqlite.lock().unwrap().record(&source.file, x),
+            None => Ok(()),
+        }
+    })?;
+    let report = Report::new(evaled_assertions, parse_errors);
+
+    if let Some(sqlite) = sqlite {
+        sqlite.into_inner().unwrap().finish(&report)?;
+    }
+    if let Some(path) = &args.parquet_hits {
+        let mut hits = ParquetHits::default();
+        for x in observed {
+            hits.merge(x);
+        }
+        let mut file = output::create(path)?;
+        hits.write(&mut file)?;
+        file.flush()?;
+    }
+    Ok(())
+}
+
+fn events(args: EventsArgs) -> Result<()> {
+    let mut out = output::create(&args.output)?;
+    match args.format {
+        EventFormat::Jsonl => {},
+        EventFormat::Csv => output::csv::write_event_header(',', &mut out)?,
+        EventFormat::Tsv => output::csv::write_event_header('\t', &mut out)?,
+    }
+    for input_file in &args.input.files()? {
+        let reader = args.input.open(input_file)?;
+        let mut skipped = 0;
+        let mut lines = parse_lines(reader)
+            .strict(args.input.strict)
+            .events(args.events.filter())
+            .timestamp_field(args.input.timestamp_field.clone());
+        while let Some(x) = lines.next() {
+            let x = match x {
+                Ok(x) => x,
+                Err(e) if !args.input.strict && e.is::<ParseError>() => {
+                    skipped += 1;
+                    continue;
+                },
+                Err(e) => return Err(e.context(format!("unable to parse {}", input_file))),
+            };
+            let source = Source { file: input_file.to_string(), line: lines.line() };
+            let Some(event) = TimelineEvent::new(&source, &x) else {
+                continue;
+            };
+            if !args.name.is_empty() && !args.name.iter().any(|x| x.matches(&event.event_name)) {
+                continue;
+            }
+            match args.format {
+                EventFormat::Jsonl => output::jsonl::write_event(&event, &mut out)?,
+                EventFormat::Csv => output::csv::write_event(&event, ',', &mut out)?,
+                EventFormat::Tsv => output::csv::write_event(&event, '\t', &mut out)?,
+            }
+        }
+        if skipped > 0 {
+            eprintln!("SKIPPED: {} unparseable lines in {}", skipped, input_file);
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn diff(args: DiffArgs) -> Result<ExitCode> {
+    let before = read_run(&args.before)?;
+    let after = read_run(&args.after)?;
+    let diff = Diff::new(before, after);
+
+    let mut out = output::create(&args.output)?;
+    match args.format {
+        TextFormat::Text => output::console::write_diff(&diff, &mut out)?,
+        TextFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &diff)?;
+            writeln!(out)?;
+        },
+    }
+    out.flush()?;
+    Ok(if args.fail_on_regression && diff.has_regression() { ExitCode::from(EXIT_FAILED) } else { ExitCode::SUCCESS })
+}
+
+fn merge(args: MergeArgs) -> Result<ExitCode> {
+    let mut runs = Vec::new();
+    for path in &args.reports {
+        let mut text = String::new();
+        crunch::input::open(path)?.read_to_string(&mut text)?;
+        let assertions: Vec<EvaluatedAssertion> = crunch::read_assertions(&text).with_context(|| format!("unable to read {}", path))?;
+        runs.extend(assertions);
+    }
+    let report = Report::new(merge_evaluated(runs), Vec::new());
+    let exit_code = exit_code(args.fail_on.unwrap_or(FailOn::None), &report.assertions);
+
+    match args.format {
+        Some(format) if format.writes_directory() => write_directory(format, &report, Path::new(&args.output))?,
+        _ => {
+            let mut file = output::create(&args.output)?;
+            write_format(args.format.unwrap_or(Format::Jsonl), &report, &output::Options::default(), &[], &mut file)?;
+            file.flush()?;
+        },
+    }
+    Ok(exit_code)
+}
+
+fn flaky(args: FlakyArgs) -> Result<()> {
+    let runs = args.runs.iter().map(|x| read_run(x)).collect::<Result<Vec<_>>>()?;
+    let mut assertions = crunch::flakiness(runs);
+    if args.only_flaky {
+        assertions.retain(|x| x.flaky);
+    }
+
+    let mut out = output::create(&args.output)?;
+    match args.format {
+        TextFormat::Text => output::console::write_flakiness(&args.runs, &assertions, &mut out)?,
+        TextFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &json!({ "runs": args.runs, "assertions": assertions }))?;
+            writeln!(out)?;
+        },
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn history(args: HistoryArgs) -> Result<()> {
+    let entries = output::sqlite::history(&args.db, &args.id, args.last)?;
+    let mut out = output::create(output::STDOUT)?;
+    match args.format {
+        TextFormat::Text => output::console::write_history(&args.id, &entries, &mut out)?,
+        TextFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &entries)?;
+            writeln!(out)?;
+        },
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// Read whatever was appended since the last pass and rewrite the report
+// whenever there was something, until the input stays idle for too long
+fn tail(mut args: TailArgs, config: Config) -> Result<()> {
+    config.filter.apply(&mut args.filter)?;
+    if args.output == output::STDOUT {
+        bail!("tail keeps rewriting --output, which must be a file");
+    }
+    let mut lines = parse_lines(Follow::new(crunch::input::follow(&args.input)?))
+        .timestamp_field(args.timestamp_field.clone());
+    let live = Mutex::new(Live::new(args.filter.filter()));
+    keep_writing(&live, args.format, Path::new(&args.output), args.interval, args.idle_timeout, |live| {
+        for x in lines.by_ref() {
+            live.ingest(x, &args.input)?;
+        }
+        live.span = lines.span().clone();
+        Ok(())
+    })
+}
+
+// Take SDK output over TCP, one connection per workload, and rewrite the
+// report of everything received whenever there was something new
+fn listen(mut args: ListenArgs, config: Config) -> Result<()> {
+    config.filter.apply(&mut args.filter)?;
+    if args.output == output::STDOUT {
+        bail!("listen keeps rewriting --output, which must be a file");
+    }
+    let listener = TcpListener::bind(&args.address)
+        .with_context(|| format!("unable to listen on {}", args.address))?;
+    let live = Arc::new(Mutex::new(Live::new(args.filter.filter())));
+    let accepting = live.clone();
+    let timestamp_field = args.timestamp_field.clone();
+    let idle_timeout = args.idle_timeout;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("WARNING: unable to accept a connection: {}", e);
+                    continue;
+                },
+            };
+            // A client that stays quiet that long is let go, like listen itself
+            if let Err(e) = stream.set_read_timeout(idle_timeout.filter(|x| *x > 0).map(Duration::from_secs)) {
+                eprintln!("WARNING: unable to set a read timeout: {}", e);
             }
+            let live = accepting.clone();
+            let timestamp_field = timestamp_field.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().map(|x| x.to_string()).unwrap_or_default();
+                if let Err(e) = receive(stream, &peer, &live, timestamp_field) {
+                    eprintln!("WARNING: {:#}", e);
+                }
+            });
         }
+    });
+    keep_writing(&live, args.format, Path::new(&args.output), args.interval, args.idle_timeout, |_| Ok(()))
+}
 
-        // TODO Handle requests that do not even have a catalog_entry
-        let input_entry = catalog_entry.unwrap();
+fn receive(stream: TcpStream, peer: &str, live: &Mutex<Live>, timestamp_field: Option<String>) -> Result<()> {
+    let mut lines = parse_lines(BufReader::new(stream))
+        .timestamp_field(timestamp_field);
+    while let Some(x) = lines.next() {
+        let mut live = live.lock().unwrap();
+        live.ingest(x, peer)?;
+        live.span.merge(lines.span().clone());
+    }
+    Ok(())
+}
+
+// The page served on `GET /`, which renders what `GET /report` returns
+// and what `GET /events` pushes
+const DASHBOARD: &str = include_str!("dashboard.html");
 
-        let passed: bool;
-        let mut example_details = None;
-        let mut counter_details = None;
+// How often `GET /events` checks whether the report changed, and whether
+// the client is still there
+const EVENTS_INTERVAL: Duration = Duration::from_secs(1);
 
-        match input_entry.assert_type {
-            AssertType::Always => {
-                let must_hit = input_entry.must_hit;
-                if must_hit {
-                    passed = condition_true_entry.is_some() &&  condition_false_entry.is_none();
-                } else {
-                    passed = condition_false_entry.is_none();
+// Where the report that serve answers with comes from
+enum Served {
+    // Read again for each request, so that it can be rewritten meanwhile
+    File(String),
+    Live(Box<Mutex<Live>>),
+}
+
+impl Served {
+    fn report_json(&self) -> Result<Vec<u8>> {
+        let report = match self {
+            Served::File(path) => {
+                let text = fs::read_to_string(path)
+                    .with_context(|| format!("unable to read {}", path))?;
+                if serde_json::from_str::<Value>(&text).is_ok_and(|x| x.get("assertions").is_some()) {
+                    return Ok(text.into_bytes());
                 }
-                example_details = condition_true_entry.map(|x| x.details);
-                counter_details = condition_false_entry.map(|x| x.details);
+                // A jsonl report, summarized again
+                let assertions = crunch::read_assertions(&text)
+                    .with_context(|| format!("unable to read {}", path))?;
+                Report::new(assertions, Vec::new())
             },
-            AssertType::Sometimes => {
-                passed = condition_true_entry.is_some();
-                example_details = condition_true_entry.map(|x| x.details);
-                // TODO Do we really want to show details for a sometimes that failed?
-                counter_details = condition_false_entry.map(|x| x.details);
+            Served::Live(live) => live.lock().unwrap().report(),
+        };
+        let mut body = Vec::new();
+        output::json::write(&report, &mut body)?;
+        Ok(body)
+    }
+
+    // Changes whenever the report does: the file's modification time and
+    // length, or how many lines were posted
+    fn version(&self) -> (Option<SystemTime>, u64) {
+        match self {
+            Served::File(path) => match fs::metadata(path) {
+                Ok(metadata) => (metadata.modified().ok(), metadata.len()),
+                Err(_) => (None, 0),
             },
-            AssertType::Reachability => {
-                let hit = condition_true_entry.is_some() || condition_false_entry.is_some();
-                let must_hit = input_entry.must_hit;
-                if must_hit {
-                    passed = hit;
-                    example_details =  condition_true_entry.or(condition_false_entry).map(|x| x.details);
-                } else {
-                    passed = !hit;
-                    counter_details =  condition_true_entry.or(condition_false_entry).map(|x| x.details);
-                }
+            Served::Live(live) => (None, live.lock().unwrap().lines as u64),
+        }
+    }
+}
+
+// Push the report to the client as a `report` event, then what changed as
+// an `update` event each time it changes, until the client goes away. A
+// closed connection only shows when writing to it fails, so something is
+// written on every tick
+fn push_reports(out: &mut TcpStream, served: &Served) -> Result<()> {
+    http::start_events(out)?;
+    let mut sent = None;
+    let mut assertions = HashMap::new();
+    loop {
+        let version = served.version();
+        // A file being rewritten may not read yet, try it again next time
+        let report = (sent != Some(version))
+            .then(|| served.report_json().and_then(|x| Ok(serde_json::from_slice::<Value>(&x)?)).ok())
+            .flatten();
+        let pushed = match report {
+            Some(report) => {
+                let pushed = push_report(out, report, &mut assertions, sent.is_none());
+                sent = Some(version);
+                pushed
             },
+            None => http::keep_alive(out),
+        };
+        if pushed.is_err() {
+            return Ok(());
         }
+        thread::sleep(EVENTS_INTERVAL);
+    }
+}
 
-        let evaled = Self {
-            display_type: input_entry.display_type,
-            id: input_entry.id,
-            message: input_entry.message,
-            location: input_entry.location,
-            passed,
-            example_details,
-            counter_details,
+// Send `report` whole the first time, and after that only the assertions
+// that changed since they were last sent (by id in `sent`) and the ids of
+// those that are gone, with the rest of the report. A large report mostly
+// stays the same while SDK output comes in
+fn push_report(out: &mut TcpStream, mut report: Value, sent: &mut HashMap<String, Value>, first: bool) -> Result<()> {
+    let Some(Value::Array(assertions)) = report.as_object_mut().and_then(|x| x.remove("assertions")) else {
+        bail!("report without assertions");
+    };
+    let mut changed = Vec::new();
+    let mut ids = HashSet::new();
+    for evaled in assertions {
+        let id = evaled["id"].as_str().unwrap_or_default().to_string();
+        ids.insert(id.clone());
+        if sent.get(&id) != Some(&evaled) {
+            changed.push(evaled.clone());
+            sent.insert(id, evaled);
+        }
+    }
+    let removed: Vec<String> = sent.keys().filter(|x| !ids.contains(*x)).cloned().collect();
+    for id in &removed {
+        sent.remove(id);
+    }
+    if first {
+        report["assertions"] = Value::Array(changed);
+        return http::send_event(out, "report", &report.to_string());
+    }
+    let update = json!({ "report": report, "changed": changed, "removed": removed });
+    http::send_event(out, "update", &update.to_string())
+}
+
+// Answer each HTTP request on its own thread, with the dashboard or the
+// report of the given file or of what was posted so far
+fn serve(mut args: ServeArgs, config: Config) -> Result<()> {
+    config.filter.apply(&mut args.filter)?;
+    let served = match &args.report {
+        Some(path) => Served::File(path.clone()),
+        None if args.ingest => Served::Live(Box::new(Mutex::new(Live::new(args.filter.filter())))),
+        None => bail!("nothing to serve, give a report or --ingest to take SDK output on POST /lines"),
+    };
+    // Fail now rather than on the first request
+    served.report_json()?;
+    let listener = TcpListener::bind(&args.address)
+        .with_context(|| format!("unable to listen on {}", args.address))?;
+    eprintln!("Serving on http://{}", listener.local_addr()?);
+    let served = Arc::new(served);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("WARNING: unable to accept a connection: {}", e);
+                continue;
+            },
         };
-        evaled 
+        let served = served.clone();
+        let timestamp_field = args.timestamp_field.clone();
+        thread::spawn(move || {
+            if let Err(e) = answer(stream, &served, timestamp_field) {
+                eprintln!("WARNING: {:#}", e);
+            }
+        });
     }
+    Ok(())
 }
 
+fn answer(stream: TcpStream, served: &Served, timestamp_field: Option<String>) -> Result<()> {
+    let peer = stream.peer_addr().map(|x| x.to_string()).unwrap_or_default();
+    stream.set_read_timeout(Some(http::READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut out = stream;
+    let request = match http::read_request(&mut reader) {
+        Ok(request) => request,
+        Err(e) => return http::respond(&mut out, 400, "text/plain", format!("{:#}\n", e).as_bytes()),
+    };
+    match (request.method.as_str(), request.path.as_str(), served) {
+        ("POST", "/lines", Served::Live(live)) => {
+            let received = ingest_lines(&request, &peer, live, timestamp_field)?;
+            http::respond(&mut out, 200, "application/json", format!("{}\n", received).as_bytes())
+        },
+        ("GET", "/", _) => http::respond(&mut out, 200, "text/html; charset=utf-8", DASHBOARD.as_bytes()),
+        ("GET", "/events", _) => push_reports(&mut out, served),
+        ("GET", "/report", _) => match served.report_json() {
+            Ok(body) => http::respond(&mut out, 200, "application/json", &body),
+            Err(e) => http::respond(&mut out, 500, "text/plain", format!("{:#}\n", e).as_bytes()),
+        },
+        (_, "/lines", Served::File(_)) => http::respond(&mut out, 404, "text/plain", b"not found, serve was not given --ingest\n"),
+        (_, "/" | "/lines" | "/report" | "/events", _) => http::respond(&mut out, 405, "text/plain", b"method not allowed\n"),
+        _ => http::respond(&mut out, 404, "text/plain", b"not found\n"),
+    }
+}
 
-fn group_asserts(inputs: Vec<SDKInput>) -> HashMap<String, Vec<AntithesisAssert>> {
-    let mut result  = HashMap::new();
-    for input in inputs {
-        match input {
-            SDKInput::AntithesisAssert(x) => {
-               let entry = result.entry(x.id.clone()).or_insert(Vec::new()); 
-               entry.push(x);
+// Feed the body of a POST /lines to `live`, and say how much of it was kept
+fn ingest_lines(request: &Request, peer: &str, live: &Mutex<Live>, timestamp_field: Option<String>) -> Result<Value> {
+    let mut lines = parse_lines(&request.body[..])
+        .timestamp_field(timestamp_field);
+    let mut live = live.lock().unwrap();
+    let (lines_before, errors_before) = (live.lines, live.parse_errors.len());
+    for x in lines.by_ref() {
+        live.ingest(x, peer)?;
+    }
+    live.span.merge(lines.span().clone());
+    Ok(json!({
+        "lines": live.lines - lines_before,
+        "parse_errors": live.parse_errors.len() - errors_before,
+    }))
+}
+
+// Every interval, let `read` add to `live` and rewrite the report at `path`
+// if anything was added, until nothing was for `idle_timeout` seconds
+fn keep_writing(live: &Mutex<Live>, format: Format, path: &Path, interval: u64, idle_timeout: Option<u64>, mut read: impl FnMut(&mut Live) -> Result<()>) -> Result<()> {
+    let interval = Duration::from_secs(interval);
+    let mut idle = Duration::ZERO;
+    let mut written = None;
+    loop {
+        let mut current = live.lock().unwrap();
+        read(&mut current)?;
+        if written != Some(current.lines) {
+            written = Some(current.lines);
+            idle = Duration::ZERO;
+            let report = current.report();
+            drop(current);
+            rewrite(format, &report, path)?;
+        } else if idle_timeout.is_some_and(|x| idle.as_secs() >= x) {
+            return Ok(());
+        }
+        thread::sleep(interval);
+        idle += interval;
+    }
+}
+
+// What tail, listen and serve have read so far
+struct Live {
+    filter: Filter,
+    // How many lines were read, to tell when the report is out of date
+    lines: usize,
+    grouped_assertions: Groups,
+    parse_errors: Vec<ParseError>,
+    span: Span,
+    guidance: Guidance,
+    run_info: RunInfo,
+    events: Events,
+}
+
+impl Live {
+    fn new(filter: Filter) -> Self {
+        Live {
+            filter,
+            lines: 0,
+            grouped_assertions: Groups::default(),
+            parse_errors: Vec::new(),
+            span: Span::default(),
+            guidance: Guidance::default(),
+            run_info: RunInfo::default(),
+            events: Events::default(),
+        }
+    }
+
+    // Keep a line read from `source`, setting aside one that can't be parsed
+    fn ingest(&mut self, line: Result<SDKInput>, source: &str) -> Result<()> {
+        match line {
+            Ok(x) => {
+                self.run_info.record(&x);
+                self.events.record(&x);
+                record_guidance(&mut self.guidance, &x);
+                if self.filter.keeps(&x) {
+                    merge_groups(&mut self.grouped_assertions, group_asserts(iter::once(x)));
+                }
             },
-            _ => {
-                eprintln!("IGNORE: {:?}", input);
+            Err(e) if e.is::<ParseError>() => {
+                let mut e: ParseError = e.downcast().unwrap();
+                e.file = Some(source.to_string());
+                self.parse_errors.push(e);
             },
+            Err(e) => return Err(e.context(format!("unable to read {}", source))),
         }
+        self.lines += 1;
+        Ok(())
     }
-    result
-}
-
-fn parse_lines(lines: Vec<&str>) -> Result<Vec<SDKInput>> {
-    let mut result = Vec::new();
-
-    for line in lines {
-        if line.len() < 1 { continue; }
-        let parsed: SDKInput = match serde_json::from_str(line) {
-            Ok(x) => x,
-            Err(_e) => {
-                // println!("{}", line);
-                // println!("PARSING: {:?}", e);
-                let temp: Value = serde_json::from_str(line)?; 
-                // should be Object(Map<String, Value>)
-                // in this case the Map has just one entry (top-level name used by SendEvent())
-                match temp {
-                    Value::Object(user_data) => {
-                       let mut result = None;
-                       for (event_name, details) in user_data {
-                            result = Some(SDKInput::SendEvent{
-                                event_name,
-                                details,
-                            });
-                            break;
-                       } 
-                        match result {
-                            Some(x) => x,
-                            None => bail!("no details found here")
-                        }
-                    },
-                    _ => bail!("it broke - not an Object() unable to parse JSON")
-                }
+
+    // Everything keeps growing, so report on copies
+    fn report(&self) -> Report {
+        snapshot(self.grouped_assertions.clone(), self.parse_errors.clone(), self.span.clone(), self.guidance.clone(), self.run_info.clone(), self.events.clone())
+    }
+}
+
+// What was read from one of the watched files
+struct Watched {
+    modified: SystemTime,
+    len: u64,
+    grouped_assertions: Groups,
+    parse_errors: Vec<ParseError>,
+    span: Span,
+    guidance: Guidance,
+    run_info: RunInfo,
+    events: Events,
+}
+
+// Reread the input files that were added or changed since the last pass,
+// forget those that are gone, and rewrite the merged report if any did
+fn watch(mut args: WatchArgs, config: Config) -> Result<()> {
+    config.filter.apply(&mut args.filter)?;
+    config.apply_input(&mut args.input);
+    if args.input.inputs.iter().any(|x| x == crunch::input::STDIN) {
+        bail!("watch reads directories and files, not stdin");
+    }
+    let filter = args.filter.filter();
+    fs::create_dir_all(&args.output)
+        .with_context(|| format!("unable to create {}", args.output.display()))?;
+    let report_path = if args.format.writes_directory() {
+        args.output.clone()
+    } else {
+        args.output.join(format!("report.{}", args.format.extension()))
+    };
+    let (sender, changes) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).context("unable to watch the inputs")?;
+    for input in &args.input.inputs {
+        let (path, mode) = watch_root(input, args.input.recursive);
+        watcher.watch(&path, mode).with_context(|| format!("unable to watch {}", path.display()))?;
+    }
+
+    let mut watched: BTreeMap<String, Watched> = BTreeMap::new();
+    let settle = Duration::from_millis(args.settle);
+    let mut last_change = Instant::now();
+    let mut first = true;
+    loop {
+        let files = args.input.files()?;
+        let before = watched.len();
+        watched.retain(|path, _| files.contains(path));
+        let mut changed = first || watched.len() != before;
+        for file in files {
+            // It may have been removed since it was listed
+            let Ok(metadata) = fs::metadata(&file) else {
+                continue;
+            };
+            let (modified, len) = (metadata.modified()?, metadata.len());
+            if watched.get(&file).is_some_and(|x| x.modified == modified && x.len == len) {
+                continue;
             }
+            // A file that is still being written may not read yet, try it again next time
+            match read_watched(&file, &args.input, &filter, modified, len) {
+                Ok(x) => {
+                    watched.insert(file, x);
+                    changed = true;
+                },
+                Err(e) => eprintln!("WARNING: {:#}", e),
+            }
+        }
+
+        if changed {
+            first = false;
+            last_change = Instant::now();
+            let mut grouped_assertions = Groups::default();
+            let mut parse_errors = Vec::new();
+            let mut span = Span::default();
+            let mut guidance = Guidance::default();
+            let mut run_info = RunInfo::default();
+            let mut events = Events::default();
+            for x in watched.values() {
+                merge_groups(&mut grouped_assertions, x.grouped_assertions.clone());
+                parse_errors.extend(x.parse_errors.iter().cloned());
+                span.merge(x.span.clone());
+                guidance.merge(x.guidance.clone());
+                run_info.merge(x.run_info.clone());
+                events.merge(x.events.clone());
+            }
+            let report = snapshot(grouped_assertions, parse_errors, span, guidance, run_info, events);
+            rewrite(args.format, &report, &report_path)?;
+        }
+
+        // Whatever happened, such as the report being rewritten inside a
+        // watched directory, the file stamps tell what changed
+        let change = match args.idle_timeout {
+            Some(x) => match changes.recv_timeout(Duration::from_secs(x).saturating_sub(last_change.elapsed())) {
+                Ok(change) => change,
+                Err(RecvTimeoutError::Timeout) => return Ok(()),
+                Err(RecvTimeoutError::Disconnected) => bail!("stopped watching the inputs"),
+            },
+            None => changes.recv().context("stopped watching the inputs")?,
         };
-        result.push(parsed);
+        if let Err(e) = change {
+            eprintln!("WARNING: {}", e);
+        }
+        // A file is usually written in several steps, reread it once
+        thread::sleep(settle);
+        changes.try_iter().for_each(drop);
     }
-    Ok(result)
 }
 
-fn main() -> Result<()>{
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        panic!("Usage: {} input_file output_file ...", args[0]);
+// The directory to watch for `input`: a directory itself, the directory of
+// a file (which may not exist yet) or the part of a glob before the pattern
+fn watch_root(input: &str, recursive: bool) -> (PathBuf, RecursiveMode) {
+    let path = Path::new(input);
+    if path.is_dir() {
+        let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        return (path.to_path_buf(), mode);
     }
-    let input_file = &args[1];
-    let output_file = &args[2];
-    
-    let contents = fs::read_to_string(input_file)
-        .expect("Should have been able to read the file");
-    
-    let lines = contents.split("\n");
-    let parsed = parse_lines(lines.collect())?;
-    let grouped_assertions = group_asserts(parsed);
+    let (dir, mode) = if input.contains(['*', '?', '[']) {
+        let dir = path.components().take_while(|x| !x.as_os_str().to_string_lossy().contains(['*', '?', '['])).collect();
+        (dir, RecursiveMode::Recursive)
+    } else {
+        (path.parent().map(Path::to_path_buf).unwrap_or_default(), RecursiveMode::NonRecursive)
+    };
+    if dir == Path::new("") {
+        return (PathBuf::from("."), mode);
+    }
+    (dir, mode)
+}
 
-    // After into_values() the map is no longer useable
-    let evaled_assertions: Vec<_> = grouped_assertions.into_values().map(|one_vec| EvaluatedAssertion::new(one_vec)).collect();
-    // dbg!(&evaled_assertions);
-    
-    let mut file = fs::File::create(output_file)?;
+fn read_watched(file: &str, args: &InputArgs, filter: &Filter, modified: SystemTime, len: u64) -> Result<Watched> {
+    let mut parse_errors = Vec::new();
+    let mut guidance = Guidance::default();
+    let mut run_info = RunInfo::default();
+    let mut events = Events::default();
+    let (grouped_assertions, span) = group_input(file, args, 0, &EventFilter::default(), filter, &mut parse_errors, &mut |_, x| {
+        run_info.record(x);
+        events.record(x);
+        record_guidance(&mut guidance, x);
+        Ok(())
+    })?;
+    Ok(Watched { modified, len, grouped_assertions, parse_errors, span, guidance, run_info, events })
+}
+
+// The report of what tail, watch and listen have read so far
+fn snapshot(grouped_assertions: Groups, parse_errors: Vec<ParseError>, span: Span, guidance: Guidance, run_info: RunInfo, events: Events) -> Report {
+    let evaled_assertions = grouped_assertions.into_values().map(EvaluatedAssertion::new).collect();
+    let mut report = Report::new(evaled_assertions, parse_errors);
+    report.set_guidance(guidance.finish());
+    report.set_span(span);
+    report.run_info = run_info;
+    report.events = events.finish();
+    report
+}
 
-    for evaled_assertion in evaled_assertions {
-        let s = serde_json::to_string(&evaled_assertion)?;
-        file.write_all(s.as_bytes())?;
-        file.write_all(b"\n")?;
+// Replace the previous report, so readers never see one half written
+fn rewrite(format: Format, report: &Report, path: &Path) -> Result<()> {
+    if format.writes_directory() {
+        return write_directory(format, report, path);
     }
+    output::replace(path, |out| write_format(format, report, &output::Options::default(), &[], out))
+}
 
+// The outcomes of a run, from a report or else by evaluating its SDK output
+fn read_run(path: &str) -> Result<Vec<Outcome>> {
+    let mut reader = crunch::input::open(path)?;
+    let mut text = crunch::input::read_head(&mut reader)?;
+    if crunch::is_report_start(&text) {
+        reader.read_to_string(&mut text)?;
+        return crunch::read_assertions(&text).with_context(|| format!("unable to read {}", path));
+    }
+    let evaled_assertions = evaluate_lines(io::Cursor::new(text).chain(reader), path, 0)?;
+    Ok(evaled_assertions.iter().map(Outcome::from).collect())
+}
+
+fn browse(args: TuiArgs) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        bail!("tui needs a terminal to draw on");
+    }
+    let mut assertions = read_evaluated(&args.input, args.keep_examples)?;
+    // Failures first, they are what one browses for
+    assertions.sort_by(|a, b| a.passed.cmp(&b.passed).then_with(|| a.id.cmp(&b.id)));
+    tui::run(assertions)
+}
+
+// The assertions of a report, or of SDK output evaluated on the spot
+fn read_evaluated(path: &str, keep_examples: usize) -> Result<Vec<EvaluatedAssertion>> {
+    let mut reader = crunch::input::open(path)?;
+    let mut text = crunch::input::read_head(&mut reader)?;
+    if crunch::is_report_start(&text) {
+        reader.read_to_string(&mut text)?;
+        return crunch::read_assertions(&text).with_context(|| format!("unable to read {}", path));
+    }
+    evaluate_lines(io::Cursor::new(text).chain(reader), path, keep_examples)
+}
+
+// Evaluate the SDK output of `path`, whose start was already read to tell
+// it from a report and is put back in front of the rest of it in `lines`
+fn evaluate_lines(lines: impl BufRead, path: &str, keep_examples: usize) -> Result<Vec<EvaluatedAssertion>> {
+    let mut skipped = 0;
+    let mut error = None;
+    let parsed = parse_lines(lines).filter_map(|x| match x {
+        Ok(x) => Some(x),
+        Err(e) if e.is::<ParseError>() => {
+            skipped += 1;
+            None
+        },
+        Err(e) => {
+            error.get_or_insert(e);
+            None
+        },
+    });
+    let grouped_assertions = group_asserts(parsed);
+    if let Some(e) = error {
+        return Err(e.context(format!("unable to parse {}", path)));
+    }
+    if skipped > 0 {
+        eprintln!("SKIPPED: {} unparseable lines in {}", skipped, path);
+    }
+    Ok(grouped_assertions.into_values().map(|x| EvaluatedAssertion::with_examples(x, keep_examples)).collect())
+}
+
+fn schema(args: SchemaArgs) -> Result<()> {
+    let mut out = output::create(output::STDOUT)?;
+    match args.document {
+        SchemaDocument::Report => out.write_all(crunch::schema::REPORT.as_bytes())?,
+        SchemaDocument::Assertion => {
+            serde_json::to_writer_pretty(&mut out, &crunch::schema::assertion()?)?;
+            writeln!(out)?;
+        },
+        SchemaDocument::Input => out.write_all(crunch::schema::SDK_INPUT.as_bytes())?,
+    }
+    out.flush()?;
     Ok(())
 }
+
+fn validate(args: ValidateArgs) -> Result<ExitCode> {
+    let mut errors = 0;
+    let mut linter = Linter::default();
+    let schema = if args.validate_schema { Some(InputSchema::new()?) } else { None };
+    for input_file in &args.input.files()? {
+        errors += validate_input(input_file, &args.input, schema.as_ref(), &mut linter)?;
+    }
+    let problems = linter.finish();
+    for problem in &problems {
+        let error = problem.kind.is_error() || (args.fail_on_never_exercised && problem.kind == Kind::NeverExercised);
+        let level = if error { "error" } else { "warning" };
+        println!("{}: {}[{}]: {}", problem.source, level, problem.kind.as_str(), problem.message);
+        if error {
+            errors += 1;
+        }
+    }
+    let never_exercised = problems.iter().filter(|x| x.kind == Kind::NeverExercised).count();
+    if never_exercised > 0 {
+        println!("{} registered assertions were never exercised", never_exercised);
+    }
+    Ok(if errors > 0 { ExitCode::from(EXIT_FAILED) } else { ExitCode::SUCCESS })
+}
+
+fn validate_input(input_file: &str, args: &InputArgs, schema: Option<&InputSchema>, linter: &mut Linter) -> Result<usize> {
+    let strict = args.strict;
+    let reader = args.open(input_file)?;
+    let mut lines = 0;
+    let mut asserts = 0;
+    let mut errors = 0;
+    let mut mismatched = 0;
+    let mut parsed_lines = parse_lines(reader)
+        .strict(strict)
+        .timestamp_field(args.timestamp_field.clone())
+        .namespace(args.namespace_by_file.then(|| input_file.to_string()));
+    while let Some(parsed) = parsed_lines.next() {
+        match parsed {
+            Ok(parsed) => {
+                lines += 1;
+                // A line that parsed is valid JSON, check it again against the schema
+                if let Some(schema) = schema {
+                    let mut value: Value = serde_json::from_str(parsed_lines.text().unwrap_or_default())?;
+                    if let (Some(field), Some(line)) = (&args.timestamp_field, value.as_object_mut()) {
+                        line.remove(field);
+                    }
+                    let schema_errors = schema.check(&value);
+                    for e in &schema_errors {
+                        println!("{}:{}: error: {}", input_file, parsed_lines.line(), e);
+                    }
+                    if !schema_errors.is_empty() {
+                        mismatched += 1;
+                    }
+                }
+                if let SDKInput::AntithesisAssert(assert) = &parsed {
+                    asserts += 1;
+                    linter.record(Source { file: input_file.to_string(), line: parsed_lines.line() }, assert);
+                }
+            },
+            Err(e) => {
+                let e = match e.downcast::<ParseError>() {
+                    Ok(e) if !strict => e,
+                    Ok(e) => return Err(anyhow::Error::from(e).context(format!("unable to parse {}", input_file))),
+                    Err(e) => return Err(e.context(format!("unable to read {}", input_file))),
+                };
+                println!("{}:{}: error: {}", input_file, e.line, e.error);
+                errors += 1;
+            },
+        }
+    }
+    if schema.is_some() {
+        println!("{}: {} lines parsed ({} assertions), {} unparseable, {} not matching the schema", input_file, lines, asserts, errors, mismatched);
+    } else {
+        println!("{}: {} lines parsed ({} assertions), {} unparseable", input_file, lines, asserts, errors);
+    }
+    Ok(errors + mismatched)
+}
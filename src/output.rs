@@ -0,0 +1,128 @@
+// Opening the destinations that reports are written to,
+// and writing a report in each of the supported formats.
+
+use anyhow::{ Context, Result };
+use std::ffi::OsString;
+use std::fs;
+use std::io::{ self, BufWriter, Write };
+use std::path::{ Path, PathBuf };
+
+use crate::report::GroupBy;
+use crate::sdk::Location;
+
+pub mod allure;
+pub mod azdo;
+pub mod buildkite;
+pub mod checkstyle;
+pub mod circleci;
+pub mod codequality;
+pub mod console;
+pub mod csv;
+pub mod diagnostics;
+pub mod json;
+pub mod jsonl;
+pub mod junit;
+pub mod markdown;
+pub mod parquet;
+pub mod sarif;
+pub mod sqlite;
+pub mod tap;
+pub mod teamcity;
+pub mod template;
+pub mod text;
+mod xml;
+pub mod yaml;
+
+/// Presentation settings for the human readable formats.
+#[derive(Default, Debug)]
+pub struct Options {
+    /// Use ANSI colors, for formats written to a terminal.
+    pub color: bool,
+    /// Nest the assertions under their group, with pass counts per group.
+    pub group_by: Option<GroupBy>,
+    /// List this many of the failed assertions with the most counterexample hits.
+    pub top: Option<usize>,
+    /// Directory the assertion locations are relative to, for showing the
+    /// source around each failure.
+    pub source_root: Option<PathBuf>,
+    /// Link the locations to the repository instead of relative paths.
+    pub repo: Option<RepoLink>,
+}
+
+/// A repository at one revision, where the assertion locations can be browsed.
+#[derive(Clone, Debug)]
+pub struct RepoLink {
+    /// e.g. `https://github.com/org/repo`
+    pub url: String,
+    pub rev: String,
+}
+
+impl RepoLink {
+    /// `url/blob/rev/file#Lline`, the way GitHub and GitLab link to a line
+    /// of a file at a commit, or None when the file is not known.
+    pub fn permalink(&self, location: &Location) -> Option<String> {
+        let file = location.file.as_deref()?.trim_start_matches("./").replace(' ', "%20");
+        let mut link = format!("{}/blob/{}/{}", self.url.trim_end_matches('/'), self.rev, file);
+        if let Some(line) = location.begin_line {
+            link.push_str(&format!("#L{}", line));
+        }
+        Some(link)
+    }
+}
+
+/// 64 bit FNV-1a of `s`, e.g. for ids that have to stay the same from one
+/// run to the next, which the std hashers don't promise.
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in s.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// The path that stands for standard output.
+pub const STDOUT: &str = "-";
+
+/// Create `path` for writing, where `-` means standard output.
+pub fn create(path: &str) -> Result<Box<dyn Write>> {
+    if path == STDOUT {
+        return Ok(Box::new(BufWriter::new(io::stdout().lock())));
+    }
+    let file = fs::File::create(path)
+        .with_context(|| format!("unable to create {}", path))?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+/// Write `path` through `write`, so that it is either fully replaced or left
+/// as it was: the new content goes to a temporary file next to it, which is
+/// then renamed over it.
+pub fn replace(path: &Path, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    let name = path.file_name().with_context(|| format!("not a file: {}", path.display()))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+
+    let file = fs::File::create(&temp)
+        .with_context(|| format!("unable to create {}", temp.display()))?;
+    let mut out = BufWriter::new(file);
+    let written = write(&mut out)
+        .and_then(|_| out.into_inner().map_err(|e| e.into_error().into()))
+        .and_then(|file| Ok(file.sync_all()?))
+        .and_then(|_| fs::rename(&temp, path).with_context(|| format!("unable to replace {}", path.display())));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Open `path` for appending, creating it if needed.
+pub fn append(path: &str) -> Result<Box<dyn Write>> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("unable to open {}", path))?;
+    Ok(Box::new(BufWriter::new(file)))
+}
@@ -0,0 +1,132 @@
+use anyhow::{ bail, Context, Result };
+use serde_json::{ json, Value };
+use std::fs;
+use std::path::Path;
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use crate::evaluate::{ EvaluatedAssertion, FailureReason };
+use crate::output::{ fnv1a, STDOUT };
+use crate::report::Report;
+use crate::severity::Severity;
+
+/// Allure results: a `<uuid>-result.json` per assertion in the directory
+/// `dir`, with a step for its true and its false hits, and its example and
+/// counterexample details attached as `<uuid>-<n>-attachment.json`.
+///
+/// The uuid comes from the assertion id, so writing the results of the next
+/// run to the same directory replaces them, while Allure's history follows
+/// each assertion through its `historyId`.
+pub fn write(report: &Report, dir: &Path) -> Result<()> {
+    if dir == Path::new(STDOUT) {
+        bail!("the allure format writes a directory, give it with --output");
+    }
+    fs::create_dir_all(dir)
+        .with_context(|| format!("unable to create {}", dir.display()))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_millis() as f64).unwrap_or_default();
+    for evaled in &report.assertions {
+        let uuid = uuid(&evaled.id);
+        let mut attachments = Vec::new();
+        let details = [("Example details", &evaled.example_details), ("Counterexample details", &evaled.counter_details)];
+        for (name, details) in details {
+            let Some(details) = details else {
+                continue;
+            };
+            let source = format!("{}-{}-attachment.json", uuid, attachments.len());
+            write_json(&dir.join(&source), details)?;
+            attachments.push(json!({ "name": name, "source": source, "type": "application/json" }));
+        }
+
+        let millis = |x: Option<f64>| x.map_or(now, |seconds| seconds * 1000.0) as i64;
+        let location = &evaled.location;
+        let labels: Vec<Value> = [
+            ("suite", location.file()),
+            ("subSuite", location.function()),
+            ("tag", &evaled.display_type),
+            ("severity", severity(evaled.severity)),
+            ("framework", "antithesis"),
+        ].into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+        let result = json!({
+            "uuid": uuid,
+            "historyId": format!("{:016x}", fnv1a(&evaled.id)),
+            "testCaseId": format!("{:016x}", fnv1a(&evaled.id)),
+            "name": evaled.message,
+            "fullName": evaled.id,
+            "status": status(evaled),
+            "statusDetails": status_details(evaled),
+            "stage": "finished",
+            "start": millis(evaled.first_hit_at.as_ref().map(|x| x.seconds)),
+            "stop": millis(evaled.last_hit_at.as_ref().map(|x| x.seconds)),
+            "labels": labels,
+            "steps": steps(evaled),
+            "attachments": attachments,
+        });
+        write_json(&dir.join(format!("{}-result.json", uuid)), &result)?;
+    }
+    Ok(())
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<()> {
+    let text = serde_json::to_string_pretty(value)?;
+    fs::write(path, text).with_context(|| format!("unable to write {}", path.display()))
+}
+
+// Shaped like a UUID, which is all Allure looks for
+fn uuid(id: &str) -> String {
+    let hex = format!("{:016x}{:016x}", fnv1a(id), fnv1a(&format!("crunch:{}", id)));
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn status(evaled: &EvaluatedAssertion) -> &'static str {
+    if evaled.passed {
+        "passed"
+    } else if evaled.unevaluated || evaled.expected_failure {
+        "skipped"
+    } else {
+        "failed"
+    }
+}
+
+fn status_details(evaled: &EvaluatedAssertion) -> Value {
+    let message = if evaled.unevaluated {
+        format!("unknown assert type {}", evaled.assert_type.as_str())
+    } else if evaled.expected_failure {
+        "expected failure".to_string()
+    } else if let Some(reason) = evaled.failure_reason {
+        format!("{} assertion failed: {}", evaled.display_type, reason.describe())
+    } else {
+        return json!({});
+    };
+    match evaled.hint {
+        Some(hint) => json!({ "message": message, "trace": hint }),
+        None => json!({ "message": message }),
+    }
+}
+
+// A step for the true hits and one for the false hits, failed when those
+// hits are what failed the assertion
+fn steps(evaled: &EvaluatedAssertion) -> Vec<Value> {
+    let failing = |condition: bool| match evaled.failure_reason {
+        Some(FailureReason::CounterexampleFound) => !condition,
+        Some(FailureReason::UnexpectedlyReached) => true,
+        _ => false,
+    };
+    [(true, evaled.true_count), (false, evaled.false_count)].into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(condition, count)| json!({
+            "name": format!("{} hits with condition {}", count, condition),
+            "status": if failing(condition) && !evaled.expected_failure { "failed" } else { "passed" },
+            "stage": "finished",
+        }))
+        .collect()
+}
+
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "critical",
+        Severity::Warning => "normal",
+        Severity::Info => "minor",
+    }
+}
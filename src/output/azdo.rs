@@ -0,0 +1,33 @@
+use anyhow::{ Result };
+use std::io::Write;
+
+use crate::output::diagnostics::problems;
+use crate::report::Report;
+
+/// Azure Pipelines logging commands, a `task.logissue` per failed assertion
+/// with the same failures as the `diagnostics` format. `info` failures are
+/// logged as warnings, which is the lowest level Azure Pipelines has.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    for problem in problems(report) {
+        let location = &problem.evaled.location;
+        let mut properties = vec![format!("type={}", if problem.level == "error" { "error" } else { "warning" })];
+        if let Some(file) = &location.file {
+            properties.push(format!("sourcepath={}", escape_property(file)));
+            properties.push(format!("linenumber={}", problem.line));
+            properties.push(format!("columnnumber={}", problem.column));
+        }
+        properties.push(format!("code={}", escape_property(&problem.evaled.id)));
+        writeln!(out, "##vso[task.logissue {}]{}", properties.join(";"), escape_data(&problem.message))?;
+    }
+    Ok(())
+}
+
+// Line breaks would end the command, `%` starts an escape of its own
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%AZP25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+// Property values also can't contain the separators
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(';', "%3B").replace(']', "%5D")
+}
@@ -0,0 +1,46 @@
+use anyhow::{ bail, Context, Result };
+use std::io::Write;
+use std::process::{ Command, Stdio };
+
+use crate::output::{ markdown, Options };
+use crate::report::Report;
+use crate::severity::Severity;
+
+/// The Markdown report, as given to `buildkite-agent annotate`.
+pub fn write(report: &Report, options: &Options, out: &mut dyn Write) -> Result<()> {
+    markdown::write(report, options, out)
+}
+
+/// The annotation style for the results: `error` when an assertion failed
+/// with an error severity, `warning` for any other failure, else `success`.
+/// Expected failures and unevaluated assertions don't count.
+pub fn style(report: &Report) -> &'static str {
+    let failed: Vec<_> = report.assertions.iter()
+        .filter(|x| !x.passed && !x.expected_failure && !x.unevaluated)
+        .collect();
+    if failed.iter().any(|x| x.severity == Severity::Error) {
+        "error"
+    } else if !failed.is_empty() {
+        "warning"
+    } else {
+        "success"
+    }
+}
+
+/// Annotate the running Buildkite build with the report, through
+/// `buildkite-agent`, replacing the annotation of an earlier crunch run.
+pub fn annotate(report: &Report, options: &Options) -> Result<()> {
+    let mut body = Vec::new();
+    write(report, options, &mut body)?;
+    let mut agent = Command::new("buildkite-agent")
+        .args(["annotate", "--context", "crunch", "--style", style(report)])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("unable to run buildkite-agent")?;
+    agent.stdin.take().expect("stdin is piped").write_all(&body)?;
+    let status = agent.wait()?;
+    if !status.success() {
+        bail!("buildkite-agent annotate failed: {}", status);
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anyhow::{ Result };
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::output::diagnostics::{ file, problems, Problem };
+use crate::output::xml::{ escape, HEADER };
+use crate::report::Report;
+
+/// Checkstyle XML with a `<file>` per source file and an `<error>` per failed
+/// assertion, with the same severities as the `diagnostics` format. Passed
+/// assertions and expected failures are left out.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let mut files: BTreeMap<String, Vec<Problem>> = BTreeMap::new();
+    for problem in problems(report) {
+        files.entry(file(problem.evaled)).or_default().push(problem);
+    }
+
+    out.write_all(HEADER.as_bytes())?;
+    writeln!(out, "<checkstyle version=\"4.3\">")?;
+    for (file, problems) in files {
+        writeln!(out, "  <file name=\"{}\">", escape(&file))?;
+        for problem in problems {
+            writeln!(out, "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"crunch.{}\"/>",
+                problem.line, problem.column, problem.level, escape(&problem.message), escape(&problem.evaled.display_type))?;
+        }
+        writeln!(out, "  </file>")?;
+    }
+    writeln!(out, "</checkstyle>")?;
+    Ok(())
+}
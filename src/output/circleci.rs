@@ -0,0 +1,18 @@
+use anyhow::{ bail, Context, Result };
+use std::fs;
+use std::path::Path;
+
+use crate::output::{ junit, replace, STDOUT };
+use crate::report::Report;
+
+/// JUnit XML laid out for CircleCI's `store_test_results`, which names the
+/// suites in the Tests tab after the directory: `<dir>/crunch/results.xml`.
+pub fn write(report: &Report, dir: &Path) -> Result<()> {
+    if dir == Path::new(STDOUT) {
+        bail!("the circleci format writes a directory, give it with --output");
+    }
+    let dir = dir.join("crunch");
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("unable to create {}", dir.display()))?;
+    replace(&dir.join("results.xml"), |out| junit::write(report, out))
+}
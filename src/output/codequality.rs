@@ -0,0 +1,28 @@
+use anyhow::{ Result };
+use serde_json::{ json, Value };
+use std::io::Write;
+
+use crate::output::diagnostics::{ file, problems };
+use crate::output::fnv1a;
+use crate::report::Report;
+
+/// The Code Climate issues GitLab reads as a Code Quality report, one per
+/// failed assertion, with the same failures as the `diagnostics` format.
+///
+/// The fingerprint is a hash of the assertion id alone, so the same failure
+/// in the next pipeline is recognized even when its line has moved.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let issues: Vec<Value> = problems(report).map(|problem| json!({
+        "description": problem.message,
+        "check_name": problem.evaled.display_type,
+        "fingerprint": format!("{:016x}", fnv1a(&problem.evaled.id)),
+        "severity": match problem.level { "error" => "major", "warning" => "minor", _ => "info" },
+        "location": {
+            "path": file(problem.evaled),
+            "lines": { "begin": problem.line },
+        },
+    })).collect();
+    serde_json::to_writer_pretty(&mut *out, &issues)?;
+    writeln!(out)?;
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use anyhow::{ Result };
+use std::io::Write;
+
+use crate::diff::{ Change, Diff, Outcome };
+use crate::evaluate::{ EvaluatedAssertion, FirstFailure, Status };
+use crate::flaky::Flakiness;
+use crate::output::sqlite::HistoryEntry;
+use crate::output::Options;
+use crate::report::Report;
+use crate::severity::Severity;
+
+const HEADER: [&str; 4] = ["ID", "TYPE", "STATUS", "LOCATION"];
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+pub(crate) fn status(evaled: &EvaluatedAssertion) -> &'static str {
+    match evaled.severity {
+        _ if evaled.passed => "passed",
+        _ if evaled.unevaluated => "unevaluated",
+        _ if evaled.expected_failure => "xfail",
+        _ if evaled.status == Status::NotHit => "not hit",
+        _ if evaled.status == Status::NotReached => "not reached",
+        Severity::Error => "FAILED",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+    }
+}
+
+// Wraps text in ANSI styles, or leaves it alone when color is off
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// A compact table of the assertions followed by the totals, for reading in
+/// a terminal. With `color`, the header is bold and the status is green or red.
+/// With `group_by`, the rows of each group follow a line with its pass count.
+/// With `top`, the failures with the most counterexample hits follow the table.
+pub fn write(report: &Report, options: &Options, out: &mut dyn Write) -> Result<()> {
+    let style = Style { color: options.color };
+    let row = |x: &EvaluatedAssertion| -> [String; 4] {
+        [
+            x.id.clone(),
+            x.display_type.clone(),
+            status(x).to_string(),
+            x.location.to_string(),
+        ]
+    };
+
+    let mut widths = HEADER.map(str::len);
+    for cells in report.assertions.iter().map(row) {
+        for (width, cell) in widths.iter_mut().zip(&cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    // Pad before painting so the escape codes don't throw off the alignment
+    let pad = |row: &[String; 4]| -> [String; 4] {
+        std::array::from_fn(|i| format!("{:<width$}", row[i], width = widths[i]))
+    };
+    let header = pad(&HEADER.map(str::to_string));
+    writeln!(out, "{}", style.paint(BOLD, header.join("  ").trim_end()))?;
+    let write_rows = |assertions: &[&EvaluatedAssertion], out: &mut dyn Write| -> Result<()> {
+        for evaled in assertions {
+            let mut cells = pad(&row(evaled));
+            let color = match status(evaled) {
+                "passed" => Some(GREEN),
+                "FAILED" => Some(RED),
+                "warning" | "not hit" | "not reached" => Some(YELLOW),
+                _ => None,
+            };
+            if let Some(color) = color {
+                cells[2] = style.paint(color, &cells[2]);
+            }
+            writeln!(out, "{}", cells.join("  ").trim_end())?;
+        }
+        Ok(())
+    };
+    match options.group_by {
+        Some(group_by) => for (group, assertions) in report.grouped(group_by) {
+            let passed = assertions.iter().filter(|x| x.passed).count();
+            writeln!(out)?;
+            writeln!(out, "{} ({}/{} passed)", style.paint(BOLD, if group.is_empty() { "(none)" } else { group }), passed, assertions.len())?;
+            write_rows(&assertions, out)?;
+        },
+        None => write_rows(&report.assertions.iter().collect::<Vec<_>>(), out)?,
+    }
+
+    if let Some(n) = options.top {
+        let top = report.top_failing(n);
+        if !top.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", style.paint(BOLD, &format!("Top {} failing", top.len())))?;
+            for evaled in top {
+                writeln!(out, "{:>8}  {}  {}", evaled.counterexample_hits(), evaled.id, evaled.location)?;
+            }
+        }
+    }
+
+    let summary = &report.summary;
+    writeln!(out)?;
+    write!(out, "{} assertions: {} passed, {} failed, {} never hit",
+        style.paint(BOLD, &summary.total.to_string()),
+        style.paint(GREEN, &summary.passed.to_string()),
+        style.paint(if summary.failed > 0 { RED } else { GREEN }, &summary.failed.to_string()),
+        summary.never_hit)?;
+    if summary.expected_failures > 0 {
+        write!(out, ", {} expected failures", summary.expected_failures)?;
+    }
+    if summary.unevaluated > 0 {
+        write!(out, ", {} unevaluated", summary.unevaluated)?;
+    }
+    if let Some(duration) = summary.duration_seconds {
+        write!(out, " over {:.1}s", duration)?;
+    }
+    match &summary.first_failure {
+        Some(FirstFailure { after_seconds: Some(after), .. }) => write!(out, ", first failure after {:.1}s", after)?,
+        Some(first) => write!(out, ", first failure at line {}", first.line)?,
+        None => {},
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// The changes between two runs, under each kind of change.
+pub fn write_diff(diff: &Diff, out: &mut dyn Write) -> Result<()> {
+    write_changes("Newly failing", &diff.newly_failing, out)?;
+    write_changes("Newly passing", &diff.newly_passing, out)?;
+    write_outcomes("Added", &diff.added, out)?;
+    write_outcomes("Removed", &diff.removed, out)?;
+    writeln!(out, "{} newly failing, {} newly passing, {} added, {} removed",
+        diff.newly_failing.len(), diff.newly_passing.len(), diff.added.len(), diff.removed.len())?;
+    Ok(())
+}
+
+fn write_changes(title: &str, changes: &[Change], out: &mut dyn Write) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "{}:", title)?;
+    for change in changes {
+        writeln!(out, "  {} ({} -> {})", change.id, change.before.as_str(), change.after.as_str())?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_outcomes(title: &str, outcomes: &[Outcome], out: &mut dyn Write) -> Result<()> {
+    if outcomes.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "{}:", title)?;
+    for outcome in outcomes {
+        writeln!(out, "  {} ({})", outcome.id, outcome.status.as_str())?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// A matrix of the status of each assertion in each run, numbered from 1 in
+/// the order of `runs`, with the flakiness score of each assertion.
+pub fn write_flakiness(runs: &[String], assertions: &[Flakiness], out: &mut dyn Write) -> Result<()> {
+    for (i, run) in runs.iter().enumerate() {
+        writeln!(out, "{}: {}", i + 1, run)?;
+    }
+    writeln!(out)?;
+
+    let mut header = vec!["ID".to_string(), "TYPE".to_string()];
+    header.extend((1..=runs.len()).map(|x| x.to_string()));
+    header.push("SCORE".to_string());
+    let rows: Vec<Vec<String>> = assertions.iter().map(|x| {
+        let mut cells = vec![x.id.clone(), x.display_type.clone()];
+        cells.extend(x.runs.iter().map(|x| match x {
+            Some(Status::Passed) => "pass".to_string(),
+            Some(Status::Unevaluated) => "?".to_string(),
+            Some(_) => "FAIL".to_string(),
+            None => "-".to_string(),
+        }));
+        cells.push(format!("{:.2}", x.score));
+        cells
+    }).collect();
+
+    let mut widths: Vec<usize> = header.iter().map(String::len).collect();
+    for cells in &rows {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for cells in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = cells.iter().zip(&widths).map(|(x, width)| format!("{:<width$}", x, width = width)).collect();
+        writeln!(out, "{}", cells.join("  ").trim_end())?;
+    }
+
+    let flaky = assertions.iter().filter(|x| x.flaky).count();
+    writeln!(out)?;
+    writeln!(out, "{} assertions over {} runs: {} flaky", assertions.len(), runs.len(), flaky)?;
+    Ok(())
+}
+
+/// The result of one assertion in each run, oldest first, and how often it passed.
+pub fn write_history(id: &str, entries: &[HistoryEntry], out: &mut dyn Write) -> Result<()> {
+    let result = |x: &HistoryEntry| match (x.hit, x.passed) {
+        (_, Some(true)) => "passed",
+        (Some(false), Some(false)) => "never hit",
+        (_, Some(false)) => "FAILED",
+        _ => "-",
+    };
+    // Runs are shown by their --run-id when they have one
+    let name = |x: &HistoryEntry| x.run_name.clone().unwrap_or_else(|| x.run_id.to_string());
+    let width = entries.iter().map(|x| name(x).chars().count()).max().unwrap_or(0).max(3);
+    writeln!(out, "{:<width$}  {:<19}  RESULT", "RUN", "CREATED", width = width)?;
+    for entry in entries {
+        writeln!(out, "{:<width$}  {:<19}  {}", name(entry), entry.created_at, result(entry), width = width)?;
+    }
+    let present = entries.iter().filter(|x| x.passed.is_some()).count();
+    let passed = entries.iter().filter(|x| x.passed == Some(true)).count();
+    writeln!(out)?;
+    writeln!(out, "{}: passed in {} of the {} runs that had it (of the last {})", id, passed, present, entries.len())?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::{ Result };
+use serde_json::{ Value };
+use std::io::Write;
+
+use crate::evaluate::EvaluatedAssertion;
+use crate::events::TimelineEvent;
+use crate::report::Report;
+
+const COLUMNS: &[&str] = &["id", "display_type", "message", "file", "function", "line", "passed"];
+
+const EVENT_COLUMNS: &[&str] = &["file", "line", "event_name", "timestamp", "details"];
+
+/// One row per assertion, separated by `delimiter` (`,` for CSV, `\t` for TSV).
+///
+/// Each of `detail_keys` adds a column holding that key of the counterexample
+/// details, or of the example details when there is no counterexample.
+/// Nested keys are separated by dots, e.g. `request.method`.
+pub fn write(report: &Report, delimiter: char, detail_keys: &[String], out: &mut dyn Write) -> Result<()> {
+    let header: Vec<String> = COLUMNS.iter().map(|x| x.to_string())
+        .chain(detail_keys.iter().cloned())
+        .collect();
+    write_row(&header, delimiter, out)?;
+
+    for evaled in &report.assertions {
+        let location = &evaled.location;
+        let mut row = vec![
+            evaled.id.clone(),
+            evaled.display_type.clone(),
+            evaled.message.clone(),
+            location.file().to_string(),
+            location.function().to_string(),
+            location.begin_line.map(|x| x.to_string()).unwrap_or_default(),
+            evaled.passed.to_string(),
+        ];
+        row.extend(detail_keys.iter().map(|key| detail(evaled, key)));
+        write_row(&row, delimiter, out)?;
+    }
+    Ok(())
+}
+
+/// The header row for [`write_event`].
+pub fn write_event_header(delimiter: char, out: &mut dyn Write) -> Result<()> {
+    let header: Vec<String> = EVENT_COLUMNS.iter().map(|x| x.to_string()).collect();
+    write_row(&header, delimiter, out)
+}
+
+/// One row for a user event, with its details as JSON.
+pub fn write_event(event: &TimelineEvent, delimiter: char, out: &mut dyn Write) -> Result<()> {
+    let timestamp = match &event.timestamp {
+        None => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    };
+    let row = vec![
+        event.file.clone(),
+        event.line.to_string(),
+        event.event_name.clone(),
+        timestamp,
+        event.details.to_string(),
+    ];
+    write_row(&row, delimiter, out)
+}
+
+fn detail(evaled: &EvaluatedAssertion, key: &str) -> String {
+    let details = evaled.counter_details.as_ref().or(evaled.example_details.as_ref());
+    let value = key.split('.').try_fold(details.unwrap_or(&Value::Null), |value, key| value.get(key));
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn write_row(row: &[String], delimiter: char, out: &mut dyn Write) -> Result<()> {
+    let fields: Vec<String> = row.iter().map(|x| field(x, delimiter)).collect();
+    writeln!(out, "{}", fields.join(&delimiter.to_string()))?;
+    Ok(())
+}
+
+// Quote the way RFC 4180 does, only when needed
+fn field(s: &str, delimiter: char) -> String {
+    if s.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{ Result };
+use serde_json::{ json, Map, Value };
+use std::io::Write;
+
+use crate::evaluate::{ EvaluatedAssertion, FailureReason };
+use crate::report::Report;
+use crate::severity::Severity;
+
+/// One `file:line:column: level: message` line per failed assertion, the
+/// way compilers report problems, so editors and CI log parsers pick them up.
+/// Assertions that failed only by never being hit are warnings, the others
+/// follow their severity (`error`, `warning` or `info`). Expected failures
+/// are left out.
+///
+/// The line and column are always there, `1` when the SDK didn't know them,
+/// so a VS Code task can read the failures into the Problems panel with:
+///
+/// ```json
+/// "problemMatcher": {
+///   "owner": "crunch",
+///   "fileLocation": ["relative", "${workspaceFolder}"],
+///   "pattern": {
+///     "regexp": "^(.*):(\\d+):(\\d+): (error|warning|info): (.*)$",
+///     "file": 1, "line": 2, "column": 3, "severity": 4, "message": 5
+///   }
+/// }
+/// ```
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    for problem in problems(report) {
+        let evaled = problem.evaled;
+        writeln!(out, "{}:{}:{}: {}: {}", file(evaled), problem.line, problem.column, problem.level, problem.message)?;
+    }
+    Ok(())
+}
+
+/// The same failures as [`write`], as a JSON object of the diagnostics of
+/// each file in the shape of VS Code's `Diagnostic`: a zero-based `range`,
+/// `severity` 0 (error), 1 (warning) or 2 (information), `message`,
+/// `source` and the assertion id as `code`.
+pub fn write_vscode(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let mut by_file = Map::new();
+    for problem in problems(report) {
+        let position = json!({ "line": problem.line - 1, "character": problem.column - 1 });
+        let diagnostic = json!({
+            "range": { "start": position, "end": position },
+            "severity": match problem.level { "error" => 0, "warning" => 1, _ => 2 },
+            "message": problem.message,
+            "source": "crunch",
+            "code": problem.evaled.id,
+        });
+        let diagnostics = by_file.entry(file(problem.evaled)).or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(diagnostics) = diagnostics {
+            diagnostics.push(diagnostic);
+        }
+    }
+    serde_json::to_writer_pretty(&mut *out, &by_file)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// A failed assertion as a problem at a line of its file.
+pub(crate) struct Problem<'a> {
+    pub evaled: &'a EvaluatedAssertion,
+    /// 1 when not known.
+    pub line: i32,
+    /// 1 when not known.
+    pub column: i32,
+    /// `error`, `warning` or `info`.
+    pub level: &'static str,
+    pub message: String,
+}
+
+/// The failed assertions of `report` that are not expected failures, see [`write`].
+pub(crate) fn problems(report: &Report) -> impl Iterator<Item = Problem<'_>> {
+    report.assertions.iter()
+        .filter(|x| !x.passed && !x.unevaluated && !x.expected_failure)
+        .map(|evaled| {
+            let level = match evaled.severity {
+                _ if !evaled.hit => "warning",
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "info",
+            };
+            let reason = evaled.failure_reason.map_or("failed", FailureReason::describe);
+            let location = &evaled.location;
+            Problem {
+                evaled,
+                line: location.begin_line.filter(|x| *x > 0).unwrap_or(1),
+                column: location.begin_column.filter(|x| *x > 0).unwrap_or(1),
+                level,
+                message: format!("{} {:?}: {} [{}]", evaled.display_type, evaled.message.replace(['\r', '\n'], " "), reason, evaled.id),
+            }
+        })
+}
+
+pub(crate) fn file(evaled: &EvaluatedAssertion) -> String {
+    evaled.location.file.as_deref().unwrap_or("(unknown)").to_string()
+}
@@ -0,0 +1,11 @@
+use anyhow::{ Result };
+use std::io::Write;
+
+use crate::report::Report;
+
+/// The whole report as a single JSON document.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *out, report)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anyhow::{ Result };
+use serde_json::json;
+use std::io::Write;
+
+use crate::events::TimelineEvent;
+use crate::report::Report;
+
+/// A `{"run_info": {...}}` line when the input said which SDK wrote it,
+/// then one JSON line per assertion, followed by a `{"guidance": [...]}` line
+/// when the input had guidance, an `{"events": [...]}` line when it had user
+/// events, a `{"timeline": [...]}` line with `--events-timeline`, and a
+/// `{"parse_errors": [...]}` line when some of the input could not be parsed.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    if !report.run_info.is_empty() {
+        serde_json::to_writer(&mut *out, &json!({ "run_info": report.run_info }))?;
+        out.write_all(b"\n")?;
+    }
+    for evaled_assertion in &report.assertions {
+        serde_json::to_writer(&mut *out, evaled_assertion)?;
+        out.write_all(b"\n")?;
+    }
+    if !report.guidance.is_empty() {
+        serde_json::to_writer(&mut *out, &json!({ "guidance": report.guidance }))?;
+        out.write_all(b"\n")?;
+    }
+    if !report.events.is_empty() {
+        serde_json::to_writer(&mut *out, &json!({ "events": report.events }))?;
+        out.write_all(b"\n")?;
+    }
+    if !report.timeline.is_empty() {
+        serde_json::to_writer(&mut *out, &json!({ "timeline": report.timeline }))?;
+        out.write_all(b"\n")?;
+    }
+    if !report.parse_errors.is_empty() {
+        serde_json::to_writer(&mut *out, &json!({ "parse_errors": report.parse_errors }))?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// One JSON line for a user event.
+pub fn write_event(event: &TimelineEvent, out: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer(&mut *out, event)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anyhow::{ Result };
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::evaluate::EvaluatedAssertion;
+use crate::output::xml::{ escape, HEADER };
+use crate::report::Report;
+
+/// JUnit XML with one `<testsuite>` per source file and one `<testcase>` per
+/// assertion. Failed assertions carry their counterexample details.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let mut suites: BTreeMap<&str, Vec<&EvaluatedAssertion>> = BTreeMap::new();
+    for evaled in &report.assertions {
+        suites.entry(evaled.location.file()).or_default().push(evaled);
+    }
+
+    out.write_all(HEADER.as_bytes())?;
+    writeln!(out, "<testsuites name=\"crunch\" tests=\"{}\" failures=\"{}\">",
+        report.summary.total, report.summary.failed)?;
+    for (file, assertions) in suites {
+        let failures = assertions.iter().filter(|x| !x.passed && !x.expected_failure && !x.unevaluated).count();
+        writeln!(out, "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+            escape(file), assertions.len(), failures)?;
+        for evaled in assertions {
+            write_testcase(evaled, out)?;
+        }
+        writeln!(out, "  </testsuite>")?;
+    }
+    writeln!(out, "</testsuites>")?;
+    Ok(())
+}
+
+fn write_testcase(evaled: &EvaluatedAssertion, out: &mut dyn Write) -> Result<()> {
+    let location = &evaled.location;
+    write!(out, "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\"",
+        escape(&evaled.message), escape(&format!("{}::{}", location.file(), location.function())),
+        escape(location.file()))?;
+    if let Some(line) = location.begin_line {
+        write!(out, " line=\"{}\"", line)?;
+    }
+    // From the first hit to the last when their timestamps are known
+    let time = match (&evaled.first_hit_at, &evaled.last_hit_at) {
+        (Some(first), Some(last)) => last.seconds - first.seconds,
+        _ => 0.0,
+    };
+    write!(out, " time=\"{:.3}\"", time)?;
+    if evaled.passed {
+        writeln!(out, "/>")?;
+        return Ok(());
+    }
+    writeln!(out, ">")?;
+    if evaled.unevaluated {
+        writeln!(out, "      <skipped message=\"{}\"/>", escape(&format!("unknown assert type {}", evaled.assert_type.as_str())))?;
+        writeln!(out, "    </testcase>")?;
+        return Ok(());
+    }
+    if evaled.expected_failure {
+        writeln!(out, "      <skipped message=\"expected failure\"/>")?;
+        writeln!(out, "    </testcase>")?;
+        return Ok(());
+    }
+    write!(out, "      <failure type=\"{}\" message=\"{}\">",
+        escape(&evaled.display_type), escape(&format!("{} assertion failed: {}", evaled.display_type, evaled.id)))?;
+    if let Some(details) = &evaled.counter_details {
+        write!(out, "{}", escape(&serde_json::to_string_pretty(details)?))?;
+    }
+    writeln!(out, "</failure>")?;
+    writeln!(out, "    </testcase>")?;
+    Ok(())
+}
@@ -0,0 +1,331 @@
+use anyhow::{ Result };
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::evaluate::{ EvaluatedAssertion, FirstFailure, Status };
+use crate::events::EventSummary;
+use crate::guidance::GuidanceSummary;
+use crate::output::{ Options, RepoLink };
+use crate::report::{ Report, RunInfo, Summary };
+use crate::severity::Severity;
+
+/// Lines of source shown before and after the line of a failed assertion.
+const SOURCE_CONTEXT: usize = 3;
+
+/// A Markdown table of the assertions, failures first, e.g. for PR comments.
+/// With `group_by` there is a table per group instead. With `source_root`,
+/// the source around each failure follows the tables.
+pub fn write(report: &Report, options: &Options, out: &mut dyn Write) -> Result<()> {
+    writeln!(out, "# Antithesis assertions")?;
+    write_run_info(&report.run_info, out)?;
+    write_summary(&report.summary, out)?;
+    if let Some(n) = options.top {
+        write_top(&report.top_failing(n), options.repo.as_ref(), out)?;
+    }
+
+    if let Some(group_by) = options.group_by {
+        for (group, assertions) in report.grouped(group_by) {
+            write_group(group, &assertions, options.repo.as_ref(), out)?;
+        }
+        write_sources(report, options, out)?;
+        write_guidance(&report.guidance, out)?;
+        return write_events(&report.events, out);
+    }
+
+    let (passed, failed): (Vec<_>, Vec<_>) = report.assertions.iter().partition(|x| x.passed);
+    let (unevaluated, failed): (Vec<_>, Vec<_>) = failed.into_iter().partition(|x| x.unevaluated);
+    let (expected, failed): (Vec<_>, Vec<_>) = failed.into_iter().partition(|x| x.expected_failure);
+    let (not_hit, failed): (Vec<_>, Vec<_>) = failed.into_iter().partition(|x| x.status == Status::NotHit);
+    let (not_reached, failed): (Vec<_>, Vec<_>) = failed.into_iter().partition(|x| x.status == Status::NotReached);
+    for (title, severity) in [("Failed", Severity::Error), ("Warnings", Severity::Warning), ("Info", Severity::Info)] {
+        let failed: Vec<_> = failed.iter().copied().filter(|x| x.severity == severity).collect();
+        write_section(title, &failed, options.repo.as_ref(), out)?;
+    }
+    write_section("Not hit", &not_hit, options.repo.as_ref(), out)?;
+    write_section("Not reached", &not_reached, options.repo.as_ref(), out)?;
+    write_section("Expected failures", &expected, options.repo.as_ref(), out)?;
+    write_section("Unevaluated", &unevaluated, options.repo.as_ref(), out)?;
+    write_section("Passed", &passed, options.repo.as_ref(), out)?;
+    write_sources(report, options, out)?;
+    write_guidance(&report.guidance, out)?;
+    write_events(&report.events, out)?;
+    Ok(())
+}
+
+fn write_run_info(run_info: &RunInfo, out: &mut dyn Write) -> Result<()> {
+    if run_info.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    let mut parts = Vec::new();
+    if let Some(run_id) = &run_info.run_id {
+        parts.push(format!("run: {}", cell(run_id)));
+    }
+    let sdks: Vec<String> = run_info.sdks.iter().map(|x| format!("{} {}", x.language, x.version)).collect();
+    if !sdks.is_empty() {
+        parts.push(format!("SDK: {}", cell(&sdks.join(", "))));
+    }
+    if let Some(setup) = &run_info.setup {
+        parts.push(format!("setup: {}", cell(&setup.status)));
+    }
+    if let Some(git) = &run_info.git {
+        let sha: String = git.sha.chars().take(12).collect();
+        match &git.branch {
+            Some(branch) => parts.push(format!("git: {} ({})", cell(branch), sha)),
+            None => parts.push(format!("git: {}", sha)),
+        }
+    }
+    for (key, value) in &run_info.labels {
+        parts.push(format!("{}: {}", cell(key), cell(value)));
+    }
+    writeln!(out, "{}", parts.join(", "))?;
+    Ok(())
+}
+
+fn write_summary(summary: &Summary, out: &mut dyn Write) -> Result<()> {
+    writeln!(out)?;
+    write!(out, "{} assertions: {} passed, {} failed, {} never hit", summary.total, summary.passed, summary.failed, summary.never_hit)?;
+    if summary.expected_failures > 0 {
+        write!(out, ", {} expected failures", summary.expected_failures)?;
+    }
+    if summary.unevaluated > 0 {
+        write!(out, ", {} unevaluated", summary.unevaluated)?;
+    }
+    if let Some(pass_rate) = summary.pass_rate {
+        write!(out, " ({:.1}% passed)", pass_rate * 100.0)?;
+    }
+    if let Some(duration) = summary.duration_seconds {
+        write!(out, " over {:.1}s", duration)?;
+    }
+    match &summary.first_failure {
+        Some(FirstFailure { after_seconds: Some(after), .. }) => write!(out, ", first failure after {:.1}s", after)?,
+        Some(first) => write!(out, ", first failure at line {}", first.line)?,
+        None => {},
+    }
+    writeln!(out)?;
+    writeln!(out)?;
+    writeln!(out, "| Type | Total | Passed | Failed | Never hit |")?;
+    writeln!(out, "| --- | ---: | ---: | ---: | ---: |")?;
+    for (assert_type, counts) in &summary.by_type {
+        writeln!(out, "| {} | {} | {} | {} | {} |", assert_type.as_str(), counts.total, counts.passed, counts.failed, counts.never_hit)?;
+    }
+    if !summary.by_language.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "| Language | Total | Passed | Failed | Never hit |")?;
+        writeln!(out, "| --- | ---: | ---: | ---: | ---: |")?;
+        for (language, counts) in &summary.by_language {
+            writeln!(out, "| {} | {} | {} | {} | {} |", cell(language), counts.total, counts.passed, counts.failed, counts.never_hit)?;
+        }
+    }
+    if !summary.by_file.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "| File | Total | Passed | Failed | Never hit |")?;
+        writeln!(out, "| --- | ---: | ---: | ---: | ---: |")?;
+        for x in &summary.by_file {
+            let file = if x.file.is_empty() { "(unknown)" } else { &x.file };
+            let counts = &x.counts;
+            writeln!(out, "| {} | {} | {} | {} | {} |", cell(file), counts.total, counts.passed, counts.failed, counts.never_hit)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_section(title: &str, assertions: &[&EvaluatedAssertion], repo: Option<&RepoLink>, out: &mut dyn Write) -> Result<()> {
+    if assertions.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "## {} ({})", title, assertions.len())?;
+    writeln!(out)?;
+    writeln!(out, "| Assertion | Type | Location |")?;
+    writeln!(out, "| --- | --- | --- |")?;
+    for evaled in assertions {
+        writeln!(out, "| {} | {} | {} |", cell(&evaled.message), cell(&evaled.display_type), location_link(evaled, repo))?;
+    }
+    Ok(())
+}
+
+fn write_top(assertions: &[&EvaluatedAssertion], repo: Option<&RepoLink>, out: &mut dyn Write) -> Result<()> {
+    if assertions.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "## Top failing ({})", assertions.len())?;
+    writeln!(out)?;
+    writeln!(out, "| Assertion | Type | Counterexamples | Location |")?;
+    writeln!(out, "| --- | --- | ---: | --- |")?;
+    for evaled in assertions {
+        writeln!(out, "| {} | {} | {} | {} |", cell(&evaled.message), cell(&evaled.display_type), evaled.counterexample_hits(), location_link(evaled, repo))?;
+    }
+    Ok(())
+}
+
+fn write_group(group: &str, assertions: &[&EvaluatedAssertion], repo: Option<&RepoLink>, out: &mut dyn Write) -> Result<()> {
+    let passed = assertions.iter().filter(|x| x.passed).count();
+    writeln!(out)?;
+    writeln!(out, "## {} ({}/{} passed)", if group.is_empty() { "(none)" } else { group }, passed, assertions.len())?;
+    writeln!(out)?;
+    writeln!(out, "| Status | Assertion | Type | Location |")?;
+    writeln!(out, "| --- | --- | --- | --- |")?;
+    for evaled in assertions {
+        let status = match evaled.severity {
+            _ if evaled.passed => "passed",
+            _ if evaled.unevaluated => "unevaluated",
+            _ if evaled.expected_failure => "expected failure",
+            _ if evaled.status == Status::NotHit => "not hit",
+            _ if evaled.status == Status::NotReached => "not reached",
+            Severity::Error => "**failed**",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        writeln!(out, "| {} | {} | {} | {} |", status, cell(&evaled.message), cell(&evaled.display_type), location_link(evaled, repo))?;
+    }
+    Ok(())
+}
+
+fn write_sources(report: &Report, options: &Options, out: &mut dyn Write) -> Result<()> {
+    let Some(root) = &options.source_root else {
+        return Ok(());
+    };
+    let Ok(root) = root.canonicalize() else {
+        return Ok(());
+    };
+    let snippets: Vec<_> = report.assertions.iter()
+        .filter(|x| !x.passed && !x.unevaluated)
+        .filter_map(|x| Some((x, snippet(&root, x)?)))
+        .collect();
+    if snippets.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "## Source ({})", snippets.len())?;
+    for (evaled, snippet) in snippets {
+        writeln!(out)?;
+        writeln!(out, "{} ({})", cell(&evaled.message), location_link(evaled, options.repo.as_ref()))?;
+        writeln!(out)?;
+        let extension = Path::new(evaled.location.file()).extension().and_then(|x| x.to_str()).unwrap_or_default();
+        writeln!(out, "```{}", extension)?;
+        write!(out, "{}", snippet)?;
+        writeln!(out, "```")?;
+    }
+    Ok(())
+}
+
+// The lines around the assertion's line in its file under `root`, the
+// assertion's own line marked, or None when the file can't be read
+// `root` is canonical, a location can't point outside of it, e.g. with `..`
+// or an absolute path
+fn snippet(root: &Path, evaled: &EvaluatedAssertion) -> Option<String> {
+    let location = &evaled.location;
+    let line = usize::try_from(location.begin_line?).ok().filter(|x| *x > 0)?;
+    let path = root.join(location.file.as_deref()?).canonicalize().ok()?;
+    if !path.starts_with(root) {
+        return None;
+    }
+    let text = fs::read_to_string(path).ok()?;
+    let first = line.saturating_sub(SOURCE_CONTEXT).max(1);
+    let lines: Vec<&str> = text.lines().skip(first - 1).take(line + SOURCE_CONTEXT + 1 - first).collect();
+    if lines.len() <= line - first {
+        return None;
+    }
+    let width = (first + lines.len() - 1).to_string().len();
+    let mut snippet = String::new();
+    for (n, text) in (first..).zip(lines) {
+        let marker = if n == line { '>' } else { ' ' };
+        // The fence can't be closed by the source itself
+        let text = text.replace("```", "`\u{200b}``");
+        snippet.push_str(&format!("{} {:>width$} | {}\n", marker, n, text, width = width));
+    }
+    Some(snippet)
+}
+
+fn write_guidance(guidance: &[GuidanceSummary], out: &mut dyn Write) -> Result<()> {
+    if guidance.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "## Guidance ({})", guidance.len())?;
+    writeln!(out)?;
+    writeln!(out, "| Guidance | Hits | Min left - right | Max left - right | True counts | Location |")?;
+    writeln!(out, "| --- | ---: | ---: | ---: | --- | --- |")?;
+    let number = |x: Option<f64>| x.map(|x| x.to_string()).unwrap_or_default();
+    for summary in guidance {
+        let true_counts: Vec<String> = summary.true_counts.iter().map(|(name, count)| format!("{}: {}", name, count)).collect();
+        writeln!(out, "| {} | {} | {} | {} | {} | {} |", cell(&summary.message), summary.hits,
+            number(summary.min_difference), number(summary.max_difference),
+            cell(&true_counts.join(", ")), cell(&summary.location.to_string()))?;
+    }
+    Ok(())
+}
+
+fn write_events(events: &[EventSummary], out: &mut dyn Write) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "## Events ({})", events.len())?;
+    writeln!(out)?;
+    writeln!(out, "| Event | Count |")?;
+    writeln!(out, "| --- | ---: |")?;
+    for summary in events {
+        writeln!(out, "| {} | {} |", cell(&summary.event_name), summary.count)?;
+    }
+    Ok(())
+}
+
+/// `[file:line](file#Lline)`, relative to wherever the Markdown is rendered,
+/// or with `repo` a permalink to the line.
+pub(crate) fn location_link(evaled: &EvaluatedAssertion, repo: Option<&RepoLink>) -> String {
+    let location = &evaled.location;
+    let Some(file) = &location.file else {
+        return cell(&location.to_string());
+    };
+    let text = match location.begin_line {
+        Some(line) => format!("{}:{}", cell(file), line),
+        None => cell(file),
+    };
+    let link = match (repo.and_then(|x| x.permalink(location)), location.begin_line) {
+        (Some(link), _) => link,
+        (None, Some(line)) => format!("{}#L{}", file.replace(' ', "%20"), line),
+        (None, None) => file.replace(' ', "%20"),
+    };
+    format!("[{}]({})", text, link)
+}
+
+// Keep a value inside its table cell
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ parse_line, SDKInput };
+
+    fn failed_at(file: &str) -> EvaluatedAssertion {
+        let line = format!(
+            r#"{{"antithesis_assert": {{"id": "a", "message": "a", "assert_type": "always", "display_type": "Always", "condition": false, "hit": true, "must_hit": true, "location": {{"file": {:?}, "begin_line": 2}}, "details": null}}}}"#,
+            file,
+        );
+        match parse_line(&line).unwrap() {
+            SDKInput::AntithesisAssert(x) => EvaluatedAssertion::new(vec![x]),
+            x => panic!("not an assertion: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn snippets_stay_in_the_source_root() {
+        let dir = std::env::temp_dir().join(format!("crunch-markdown-{}", std::process::id()));
+        fs::create_dir_all(dir.join("root/src")).unwrap();
+        fs::write(dir.join("root/src/a.rs"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.join("secret.rs"), "one\ntwo\nthree\n").unwrap();
+        let root = dir.join("root").canonicalize().unwrap();
+
+        assert_eq!(snippet(&root, &failed_at("src/a.rs")).unwrap(), "  1 | one\n> 2 | two\n  3 | three\n");
+        assert_eq!(snippet(&root, &failed_at("../secret.rs")), None);
+        assert_eq!(snippet(&root, &failed_at(&dir.join("secret.rs").to_string_lossy())), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
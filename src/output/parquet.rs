@@ -0,0 +1,143 @@
+use anyhow::{ Result };
+use parquet::data_type::{ BoolType, ByteArray, ByteArrayType, Int32Type };
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde_json::{ Value };
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::evaluate::EvaluatedAssertion;
+use crate::report::Report;
+use crate::sdk::SDKInput;
+
+// The values of one column, for all rows
+enum Column {
+    Utf8(Vec<String>),
+    OptionalUtf8(Vec<Option<String>>),
+    OptionalInt32(Vec<Option<i32>>),
+    Bool(Vec<bool>),
+}
+
+impl Column {
+    fn schema_type(&self) -> &'static str {
+        match self {
+            Column::Utf8(_) => "REQUIRED BYTE_ARRAY",
+            Column::OptionalUtf8(_) => "OPTIONAL BYTE_ARRAY",
+            Column::OptionalInt32(_) => "OPTIONAL INT32",
+            Column::Bool(_) => "REQUIRED BOOLEAN",
+        }
+    }
+}
+
+fn write_table(columns: Vec<(&str, Column)>, out: &mut dyn Write) -> Result<()> {
+    let fields: Vec<String> = columns.iter()
+        .map(|(name, column)| match column {
+            Column::Utf8(_) | Column::OptionalUtf8(_) => format!("{} {} (UTF8);", column.schema_type(), name),
+            _ => format!("{} {};", column.schema_type(), name),
+        })
+        .collect();
+    let schema = Arc::new(parse_message_type(&format!("message schema {{ {} }}", fields.join(" ")))?);
+
+    // The parquet writer needs a Send sink, so the file is built in memory
+    let mut buf = Vec::new();
+    let mut writer = SerializedFileWriter::new(&mut buf, schema, Default::default())?;
+    let mut row_group = writer.next_row_group()?;
+    for (_, column) in columns {
+        let mut col_writer = row_group.next_column()?.expect("one column writer per schema field");
+        match column {
+            Column::Utf8(values) => {
+                let values: Vec<ByteArray> = values.into_iter().map(|x| x.into_bytes().into()).collect();
+                col_writer.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+            },
+            Column::OptionalUtf8(values) => {
+                let levels: Vec<i16> = values.iter().map(|x| x.is_some() as i16).collect();
+                let values: Vec<ByteArray> = values.into_iter().flatten().map(|x| x.into_bytes().into()).collect();
+                col_writer.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)?;
+            },
+            Column::OptionalInt32(values) => {
+                let levels: Vec<i16> = values.iter().map(|x| x.is_some() as i16).collect();
+                let values: Vec<i32> = values.into_iter().flatten().collect();
+                col_writer.typed::<Int32Type>().write_batch(&values, Some(&levels), None)?;
+            },
+            Column::Bool(values) => {
+                col_writer.typed::<BoolType>().write_batch(&values, None, None)?;
+            },
+        }
+        col_writer.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    out.write_all(&buf)?;
+    Ok(())
+}
+
+// Details are stored as JSON text
+fn json(details: Option<&Value>) -> Option<String> {
+    details.filter(|x| !x.is_null()).map(Value::to_string)
+}
+
+/// The evaluated assertions as a Parquet file, one row per assertion.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let assertions = &report.assertions;
+    let strings = |f: fn(&EvaluatedAssertion) -> &str| Column::Utf8(assertions.iter().map(|x| f(x).to_string()).collect());
+    let columns = vec![
+        ("id", strings(|x| &x.id)),
+        ("assert_type", strings(|x| x.assert_type.as_str())),
+        ("display_type", strings(|x| &x.display_type)),
+        ("message", strings(|x| &x.message)),
+        ("file", Column::OptionalUtf8(assertions.iter().map(|x| x.location.file.as_deref().map(String::from)).collect())),
+        ("class", Column::OptionalUtf8(assertions.iter().map(|x| x.location.class.as_deref().map(String::from)).collect())),
+        ("function", Column::OptionalUtf8(assertions.iter().map(|x| x.location.function.as_deref().map(String::from)).collect())),
+        ("begin_line", Column::OptionalInt32(assertions.iter().map(|x| x.location.begin_line).collect())),
+        ("begin_column", Column::OptionalInt32(assertions.iter().map(|x| x.location.begin_column).collect())),
+        ("hit", Column::Bool(assertions.iter().map(|x| x.hit).collect())),
+        ("passed", Column::Bool(assertions.iter().map(|x| x.passed).collect())),
+        ("catalog_missing", Column::Bool(assertions.iter().map(|x| x.catalog_missing).collect())),
+        ("example_details", Column::OptionalUtf8(assertions.iter().map(|x| json(x.example_details.as_ref())).collect())),
+        ("counter_details", Column::OptionalUtf8(assertions.iter().map(|x| json(x.counter_details.as_ref())).collect())),
+    ];
+    write_table(columns, out)
+}
+
+/// The raw assertion hits, collected while parsing and written as one
+/// Parquet row per hit.
+#[derive(Default)]
+pub struct ParquetHits {
+    assertion_id: Vec<String>,
+    source: Vec<String>,
+    condition: Vec<bool>,
+    details: Vec<Option<String>>,
+}
+
+impl ParquetHits {
+    /// Keep `input` if it is an assertion hit read from `source`.
+    pub fn record(&mut self, source: &str, input: &SDKInput) {
+        if let SDKInput::AntithesisAssert(x) = input {
+            if x.hit {
+                self.assertion_id.push(x.group_id().into_owned());
+                self.source.push(source.to_string());
+                self.condition.push(x.condition);
+                self.details.push(json(Some(&x.details)));
+            }
+        }
+    }
+
+    /// Add the hits `other` collected, e.g. from a later input.
+    pub fn merge(&mut self, mut other: ParquetHits) {
+        self.assertion_id.append(&mut other.assertion_id);
+        self.source.append(&mut other.source);
+        self.condition.append(&mut other.condition);
+        self.details.append(&mut other.details);
+    }
+
+    pub fn write(self, out: &mut dyn Write) -> Result<()> {
+        let columns = vec![
+            ("assertion_id", Column::Utf8(self.assertion_id)),
+            ("source", Column::Utf8(self.source)),
+            ("condition", Column::Bool(self.condition)),
+            ("details", Column::OptionalUtf8(self.details)),
+        ];
+        write_table(columns, out)
+    }
+}
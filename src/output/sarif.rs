@@ -0,0 +1,79 @@
+use anyhow::{ Result };
+use serde_json::{ json, Value };
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use crate::evaluate::EvaluatedAssertion;
+use crate::report::Report;
+use crate::sdk::AssertType;
+use crate::severity::Severity;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF 2.1.0 with one result per failed Always or Reachability assertion,
+/// located at the assertion in the source. Failed Sometimes assertions have
+/// no single offending location and are left out, as are unevaluated ones.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let failed: Vec<&EvaluatedAssertion> = report.assertions.iter()
+        .filter(|x| !x.passed && !x.unevaluated && x.assert_type != AssertType::Sometimes)
+        .collect();
+
+    let rule_ids: BTreeSet<&str> = failed.iter().map(|x| x.display_type.as_str()).collect();
+    let rules: Vec<Value> = rule_ids.iter().map(|id| json!({
+        "id": id,
+        "shortDescription": { "text": format!("{} assertion failed", id) },
+    })).collect();
+    let results: Vec<Value> = failed.iter().map(|x| result(x)).collect();
+
+    let sarif = json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_writer_pretty(&mut *out, &sarif)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+fn result(evaled: &EvaluatedAssertion) -> Value {
+    let location = &evaled.location;
+    // SARIF lines and columns start at 1, leave out what the SDK didn't know
+    let mut physical = json!({ "artifactLocation": { "uri": location.file() } });
+    if let Some(line) = location.begin_line {
+        physical["region"] = json!({ "startLine": line.max(1) });
+        if let Some(column) = location.begin_column.filter(|x| *x > 0) {
+            physical["region"]["startColumn"] = json!(column);
+        }
+    }
+    let mut result = json!({
+        "ruleId": evaled.display_type,
+        "level": level(evaled.severity),
+        "message": { "text": evaled.message },
+        "locations": [{ "physicalLocation": physical }],
+        "partialFingerprints": { "assertionId": evaled.id },
+    });
+    if let Some(details) = &evaled.counter_details {
+        result["properties"] = json!({ "counter_details": details });
+    }
+    if evaled.expected_failure {
+        result["suppressions"] = json!([{ "kind": "external", "justification": "quarantined" }]);
+    }
+    result
+}
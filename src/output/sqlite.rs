@@ -0,0 +1,166 @@
+use anyhow::{ Context, Result };
+use rusqlite::{ params, Connection, OpenFlags };
+use serde::{ Serialize };
+use serde_json::{ Value };
+
+use crate::report::Report;
+use crate::sdk::{ AntithesisAssert, SDKInput };
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        run_id INTEGER PRIMARY KEY,
+        created_at TEXT NOT NULL DEFAULT (datetime('now')),
+        inputs TEXT NOT NULL,
+        run_info TEXT
+    );
+    CREATE TABLE IF NOT EXISTS assertions (
+        run_id INTEGER NOT NULL REFERENCES runs(run_id),
+        id TEXT NOT NULL,
+        assert_type TEXT NOT NULL,
+        display_type TEXT NOT NULL,
+        message TEXT NOT NULL,
+        file TEXT,
+        class TEXT,
+        function TEXT,
+        begin_line INTEGER,
+        begin_column INTEGER,
+        hit INTEGER NOT NULL,
+        passed INTEGER NOT NULL,
+        catalog_missing INTEGER NOT NULL,
+        example_details TEXT,
+        counter_details TEXT,
+        PRIMARY KEY (run_id, id)
+    );
+    CREATE TABLE IF NOT EXISTS hits (
+        run_id INTEGER NOT NULL REFERENCES runs(run_id),
+        assertion_id TEXT NOT NULL,
+        source TEXT NOT NULL,
+        condition INTEGER NOT NULL,
+        details TEXT
+    );
+    CREATE TABLE IF NOT EXISTS events (
+        run_id INTEGER NOT NULL REFERENCES runs(run_id),
+        event_name TEXT NOT NULL,
+        source TEXT NOT NULL,
+        details TEXT
+    );
+    CREATE INDEX IF NOT EXISTS hits_assertion ON hits (run_id, assertion_id);
+    CREATE INDEX IF NOT EXISTS events_name ON events (run_id, event_name);
+";
+
+/// Export of one run into a SQLite database, with normalized tables for the
+/// evaluated assertions, their individual hits, and the user events.
+///
+/// Every export is a new row in `runs`, so the same database can collect
+/// the results of many runs (see [`history`]).
+pub struct SqliteExport {
+    conn: Connection,
+    run_id: i64,
+}
+
+impl SqliteExport {
+    /// Open (or create) the database at `path` and start a new run.
+    pub fn create(path: &str, inputs: &[String]) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("unable to open {}", path))?;
+        conn.execute_batch(SCHEMA)?;
+        // Databases from before run_info was recorded
+        let has_run_info: bool = conn.query_row("SELECT COUNT(*) > 0 FROM pragma_table_info('runs') WHERE name = 'run_info'", [], |row| row.get(0))?;
+        if !has_run_info {
+            conn.execute_batch("ALTER TABLE runs ADD COLUMN run_info TEXT")?;
+        }
+        conn.execute_batch("BEGIN")?;
+        conn.execute("INSERT INTO runs (inputs) VALUES (?1)", params![inputs.join("\n")])?;
+        let run_id = conn.last_insert_rowid();
+        Ok(Self { conn, run_id })
+    }
+
+    /// Record the raw hits and events among the inputs read from `source`.
+    pub fn record(&self, source: &str, input: &SDKInput) -> Result<()> {
+        match input {
+            SDKInput::AntithesisAssert(x) if x.hit => self.record_hit(source, x),
+            SDKInput::SendEvent{event_name, details} => {
+                self.conn.prepare_cached("INSERT INTO events VALUES (?1, ?2, ?3, ?4)")?
+                    .execute(params![self.run_id, event_name, source, json(Some(details))])?;
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn record_hit(&self, source: &str, hit: &AntithesisAssert) -> Result<()> {
+        self.conn.prepare_cached("INSERT INTO hits VALUES (?1, ?2, ?3, ?4, ?5)")?
+            .execute(params![self.run_id, hit.group_id(), source, hit.condition, json(Some(&hit.details))])?;
+        Ok(())
+    }
+
+    /// Write the evaluated assertions and commit the run.
+    pub fn finish(self, report: &Report) -> Result<()> {
+        let mut insert = self.conn.prepare_cached(
+            "INSERT INTO assertions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)")?;
+        for evaled in &report.assertions {
+            let location = &evaled.location;
+            insert.execute(params![
+                self.run_id,
+                evaled.id,
+                evaled.assert_type.as_str(),
+                evaled.display_type,
+                evaled.message,
+                location.file,
+                location.class,
+                location.function,
+                location.begin_line,
+                location.begin_column,
+                evaled.hit,
+                evaled.passed,
+                evaled.catalog_missing,
+                json(evaled.example_details.as_ref()),
+                json(evaled.counter_details.as_ref()),
+            ])?;
+        }
+        drop(insert);
+        self.conn.execute("UPDATE runs SET run_info = ?1 WHERE run_id = ?2", params![serde_json::to_string(&report.run_info)?, self.run_id])?;
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+}
+
+/// The result of one assertion in one run of a database.
+#[derive(Serialize, Debug)]
+pub struct HistoryEntry {
+    pub run_id: i64,
+    pub created_at: String,
+    /// The `--run-id` the run was evaluated with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_name: Option<String>,
+    /// Whether the assertion was hit, `None` when the run didn't have it.
+    pub hit: Option<bool>,
+    /// Whether the assertion passed, `None` when the run didn't have it.
+    pub passed: Option<bool>,
+}
+
+/// The results of the assertion `id` in the last `limit` runs of the
+/// database at `path`, oldest first.
+pub fn history(path: &str, id: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("unable to open {}", path))?;
+    let mut select = conn.prepare(
+        "SELECT runs.run_id, runs.created_at, json_extract(runs.run_info, '$.run_id'), assertions.hit, assertions.passed
+         FROM runs LEFT JOIN assertions ON assertions.run_id = runs.run_id AND assertions.id = ?1
+         ORDER BY runs.run_id DESC LIMIT ?2")?;
+    let rows = select.query_map(params![id, limit as i64], |row| Ok(HistoryEntry {
+        run_id: row.get(0)?,
+        created_at: row.get(1)?,
+        run_name: row.get(2)?,
+        hit: row.get(3)?,
+        passed: row.get(4)?,
+    }))?;
+    let mut entries = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+    entries.reverse();
+    Ok(entries)
+}
+
+// Details are stored as JSON text, query them with SQLite's json functions
+fn json(details: Option<&Value>) -> Option<String> {
+    details.filter(|x| !x.is_null()).map(Value::to_string)
+}
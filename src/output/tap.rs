@@ -0,0 +1,58 @@
+use anyhow::{ Result };
+use serde::{ Serialize };
+use std::io::Write;
+
+use crate::evaluate::EvaluatedAssertion;
+use crate::report::Report;
+
+/// TAP version 13, one test point per assertion with its location (and
+/// details for failures) as a YAML diagnostics block.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    writeln!(out, "TAP version 13")?;
+    writeln!(out, "1..{}", report.assertions.len())?;
+    for (i, evaled) in report.assertions.iter().enumerate() {
+        let status = if evaled.passed || evaled.unevaluated { "ok" } else { "not ok" };
+        // A TODO test point may fail without failing the run
+        // and a SKIP test point isn't run at all
+        let directive = if evaled.unevaluated {
+            format!(" # SKIP unknown assert type {}", description(evaled.assert_type.as_str()))
+        } else if evaled.expected_failure {
+            " # TODO expected failure".to_string()
+        } else {
+            String::new()
+        };
+        writeln!(out, "{} {} - {}{}", status, i + 1, description(&evaled.message), directive)?;
+        write_diagnostics(evaled, out)?;
+    }
+    Ok(())
+}
+
+// '#' would start a directive, and the description has to stay on one line
+fn description(message: &str) -> String {
+    message.replace('#', "\\#").replace(['\r', '\n'], " ")
+}
+
+// JSON scalars and flow collections are valid YAML, so values are written as JSON
+fn yaml<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn write_diagnostics(evaled: &EvaluatedAssertion, out: &mut dyn Write) -> Result<()> {
+    let location = &evaled.location;
+    writeln!(out, "  ---")?;
+    writeln!(out, "  id: {}", yaml(&evaled.id)?)?;
+    writeln!(out, "  display_type: {}", yaml(&evaled.display_type)?)?;
+    writeln!(out, "  location:")?;
+    writeln!(out, "    file: {}", yaml(&location.file)?)?;
+    writeln!(out, "    class: {}", yaml(&location.class)?)?;
+    writeln!(out, "    function: {}", yaml(&location.function)?)?;
+    writeln!(out, "    line: {}", yaml(&location.begin_line)?)?;
+    writeln!(out, "    column: {}", yaml(&location.begin_column)?)?;
+    if !evaled.passed {
+        if let Some(details) = &evaled.counter_details {
+            writeln!(out, "  counter_details: {}", yaml(details)?)?;
+        }
+    }
+    writeln!(out, "  ...")?;
+    Ok(())
+}
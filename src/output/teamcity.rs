@@ -0,0 +1,63 @@
+use anyhow::{ Result };
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::evaluate::{ EvaluatedAssertion, FailureReason };
+use crate::report::Report;
+
+/// TeamCity service messages: a test suite per source file and a test per
+/// assertion, failed with its counterexample details, ignored when it is an
+/// expected failure or unevaluated.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    let mut suites: BTreeMap<&str, Vec<&EvaluatedAssertion>> = BTreeMap::new();
+    for evaled in &report.assertions {
+        suites.entry(evaled.location.file()).or_default().push(evaled);
+    }
+
+    for (file, assertions) in suites {
+        let suite = escape(if file.is_empty() { "(unknown)" } else { file });
+        writeln!(out, "##teamcity[testSuiteStarted name='{}']", suite)?;
+        for evaled in assertions {
+            write_test(evaled, out)?;
+        }
+        writeln!(out, "##teamcity[testSuiteFinished name='{}']", suite)?;
+    }
+    Ok(())
+}
+
+fn write_test(evaled: &EvaluatedAssertion, out: &mut dyn Write) -> Result<()> {
+    let name = escape(&evaled.id);
+    writeln!(out, "##teamcity[testStarted name='{}']", name)?;
+    if evaled.unevaluated {
+        writeln!(out, "##teamcity[testIgnored name='{}' message='{}']", name, escape(&format!("unknown assert type {}", evaled.assert_type.as_str())))?;
+    } else if !evaled.passed && evaled.expected_failure {
+        writeln!(out, "##teamcity[testIgnored name='{}' message='expected failure']", name)?;
+    } else if !evaled.passed {
+        let reason = evaled.failure_reason.map_or("failed", FailureReason::describe);
+        let details = match &evaled.counter_details {
+            Some(details) => serde_json::to_string_pretty(details)?,
+            None => evaled.hint.unwrap_or_default().to_string(),
+        };
+        writeln!(out, "##teamcity[testFailed name='{}' message='{}' details='{}']", name,
+            escape(&format!("{} assertion failed: {}", evaled.display_type, reason)), escape(&details))?;
+    }
+    writeln!(out, "##teamcity[testFinished name='{}']", name)?;
+    Ok(())
+}
+
+// The quoting of service message values, with `|` as the escape character
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '|' => escaped.push_str("||"),
+            '\'' => escaped.push_str("|'"),
+            '\n' => escaped.push_str("|n"),
+            '\r' => escaped.push_str("|r"),
+            '[' => escaped.push_str("|["),
+            ']' => escaped.push_str("|]"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,46 @@
+use anyhow::{ Context, Result };
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tera::Tera;
+
+use crate::output::RepoLink;
+use crate::report::Report;
+use crate::sdk::Location;
+
+/// Render the report through the Tera template at `path`.
+///
+/// The template sees the same fields as the `json` format: `schema_version`,
+/// `run_info`, `summary`, `assertions`, `guidance`, `events`, `timeline`
+/// (only with `--events-timeline`) and `parse_errors`. Templates named
+/// `*.html`, `*.htm` or `*.xml` have their values escaped automatically.
+///
+/// The `permalink` filter turns a location into a link to its line: into
+/// `repo` when given, otherwise relative to where the output is rendered,
+/// e.g. `<a href="{{ assertion.location | permalink }}">`.
+pub fn write(report: &Report, path: &Path, repo: Option<&RepoLink>, out: &mut dyn Write) -> Result<()> {
+    let template = fs::read_to_string(path)
+        .with_context(|| format!("unable to read template {}", path.display()))?;
+    let name = path.file_name().map(|x| x.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let mut tera = Tera::default();
+    tera.add_raw_template(&name, &template)
+        .with_context(|| format!("invalid template {}", path.display()))?;
+    let repo = repo.cloned();
+    tera.register_filter("permalink", move |value: &tera::Value, _: &HashMap<String, tera::Value>| {
+        let location: Location = tera::from_value(value.clone())?;
+        let link = repo.as_ref().and_then(|x| x.permalink(&location)).unwrap_or_else(|| {
+            let file = location.file().replace(' ', "%20");
+            match location.begin_line {
+                Some(line) => format!("{}#L{}", file, line),
+                None => file,
+            }
+        });
+        Ok(tera::Value::from(link))
+    });
+    let context = tera::Context::from_serialize(report)?;
+    tera.render_to(&name, &context, out)
+        .with_context(|| format!("unable to render template {}", path.display()))?;
+    Ok(())
+}
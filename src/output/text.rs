@@ -0,0 +1,79 @@
+use anyhow::{ Result };
+use serde_json::{ Value };
+use std::io::Write;
+
+use crate::evaluate::EvaluatedAssertion;
+use crate::output::console::status;
+use crate::report::Report;
+
+// Width of the field names, so that the values line up
+const LABEL_WIDTH: usize = 20;
+
+/// One block per assertion, its fields one per line, followed by the
+/// totals. Plain text without color, for paging through with `less`.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    for evaled in &report.assertions {
+        write_assertion(evaled, out)?;
+        writeln!(out)?;
+    }
+
+    let summary = &report.summary;
+    writeln!(out, "{}", "=".repeat(60))?;
+    let mut field = |label: &str, value: String| writeln!(out, "{:<width$}{}", label, value, width = LABEL_WIDTH);
+    field("assertions", summary.total.to_string())?;
+    field("passed", summary.passed.to_string())?;
+    field("failed", summary.failed.to_string())?;
+    field("never hit", summary.never_hit.to_string())?;
+    if summary.expected_failures > 0 {
+        field("expected failures", summary.expected_failures.to_string())?;
+    }
+    if summary.unevaluated > 0 {
+        field("unevaluated", summary.unevaluated.to_string())?;
+    }
+    if let Some(pass_rate) = summary.pass_rate {
+        field("pass rate", format!("{:.1}%", 100.0 * pass_rate))?;
+    }
+    if let Some(duration) = summary.duration_seconds {
+        field("duration", format!("{:.1}s", duration))?;
+    }
+    if !report.parse_errors.is_empty() {
+        field("parse errors", report.parse_errors.len().to_string())?;
+    }
+    Ok(())
+}
+
+fn write_assertion(evaled: &EvaluatedAssertion, out: &mut dyn Write) -> Result<()> {
+    writeln!(out, "{:<width$}{}", status(evaled), evaled.id, width = LABEL_WIDTH)?;
+    let mut field = |label: &str, value: &str| writeln!(out, "  {:<width$}{}", label, value, width = LABEL_WIDTH - 2);
+    if evaled.message != evaled.id {
+        field("message", &evaled.message)?;
+    }
+    field("type", &evaled.display_type)?;
+    field("location", &evaled.location.to_string())?;
+    field("hits", &format!("{} ({} true, {} false)", evaled.total_hits, evaled.true_count, evaled.false_count))?;
+    if let Some(reason) = evaled.failure_reason {
+        field("reason", reason.describe())?;
+    }
+    if let Some(first) = &evaled.first_failure {
+        let after = first.after_seconds.map(|x| format!(", after {:.1}s", x)).unwrap_or_default();
+        field("first failure", &format!("line {}{}", first.line, after))?;
+    }
+    if let Some(hint) = evaled.hint {
+        field("hint", hint)?;
+    }
+    if let Some(details) = evaled.counter_details.as_ref().filter(|x| !is_empty(x)) {
+        field("counterexample", &details.to_string())?;
+    }
+    if let Some(details) = evaled.example_details.as_ref().filter(|x| !is_empty(x)) {
+        field("example", &details.to_string())?;
+    }
+    Ok(())
+}
+
+fn is_empty(details: &Value) -> bool {
+    match details {
+        Value::Null => true,
+        Value::Object(x) => x.is_empty(),
+        _ => false,
+    }
+}
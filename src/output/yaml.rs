@@ -0,0 +1,11 @@
+use anyhow::{ Result };
+use std::io::Write;
+
+use crate::report::Report;
+
+/// The whole report as a single YAML document, with the same shape as the
+/// `json` format.
+pub fn write(report: &Report, out: &mut dyn Write) -> Result<()> {
+    serde_yaml::to_writer(&mut *out, report)?;
+    Ok(())
+}
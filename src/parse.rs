@@ -0,0 +1,501 @@
+use anyhow::{ Result, bail };
+use rayon::prelude::*;
+use serde::{ Serialize };
+use serde_json::{ Value };
+use std::borrow::Cow;
+use std::fmt;
+use std::collections::{ HashSet, VecDeque };
+use std::io::BufRead;
+use std::sync::Arc;
+
+use crate::events::ContextEvent;
+use crate::filter::EventFilter;
+use crate::sdk::{ AntithesisSdk, Location, SDKInput };
+use crate::timestamp::{ Span, Timestamp };
+
+// How much of an unparseable line is kept in a ParseError
+const SNIPPET_CHARS: usize = 80;
+
+/// A line of SDK output that could not be parsed.
+#[derive(Serialize, Clone, Debug)]
+pub struct ParseError {
+    /// The input the line was read from, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 1-based line number within the input.
+    pub line: usize,
+    /// Byte offset of the start of the line within the input.
+    pub offset: u64,
+    pub error: String,
+    /// The start of the offending line.
+    pub snippet: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {} (byte {}): {}", self.line, self.offset, self.error)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse one line of SDK output.
+///
+/// Lines that are not one of the known SDK messages are treated as
+/// user events (`SendEvent`); anything that is not a JSON object is an error.
+pub fn parse_line(line: &str) -> Result<SDKInput> {
+    let parsed: SDKInput = match serde_json::from_str(line) {
+        Ok(x) => x,
+        Err(_e) => {
+            let temp: Value = serde_json::from_str(line)?; 
+            // should be Object(Map<String, Value>)
+            // in this case the Map has just one entry (top-level name used by SendEvent())
+            match temp {
+                Value::Object(user_data) => {
+                    match user_data.into_iter().next() {
+                        Some((event_name, details)) => SDKInput::SendEvent{
+                            event_name,
+                            details,
+                        },
+                        None => bail!("no details found here")
+                    }
+                },
+                _ => bail!("it broke - not an Object() unable to parse JSON")
+            }
+        }
+    };
+    Ok(parsed)
+}
+
+/// Parse one line of SDK output, rejecting anything that looks like an SDK
+/// message but does not match its expected shape.
+///
+/// [`parse_line`] turns such lines into a `SendEvent` named after the message
+/// (e.g. an `antithesis_assert` with a missing field); here the serde error
+/// is returned instead. Genuine user events are still accepted.
+pub fn parse_line_strict(line: &str) -> Result<SDKInput> {
+    let error = match serde_json::from_str(line) {
+        Ok(x) => return Ok(x),
+        Err(e) => e,
+    };
+    let temp: Value = serde_json::from_str(line)?;
+    match temp {
+        Value::Object(user_data) if user_data.len() == 1 => {
+            let (event_name, details) = user_data.into_iter().next().unwrap();
+            if event_name.starts_with(SDK_PREFIX) {
+                return Err(error.into());
+            }
+            Ok(SDKInput::SendEvent{ event_name, details })
+        },
+        Value::Object(_) => bail!("expected exactly one top-level key: {}", error),
+        _ => bail!("not a JSON object"),
+    }
+}
+
+// Top-level keys reserved for messages written by the SDK itself
+const SDK_PREFIX: &str = "antithesis_";
+
+/// Lazily parse the lines of `reader`, skipping empty ones.
+///
+/// Only the current line (or batch of lines, see [`ParsedLines::parallel`])
+/// is held in memory, so arbitrarily large logs can be fed through
+/// [`group_asserts`](crate::group_asserts) as they are read.
+///
+/// A line that cannot be parsed yields a [`ParseError`] (which can be
+/// recovered with `downcast`) and parsing continues with the next line;
+/// any other error means the input could not be read.
+///
+/// Each assertion is tagged with the SDK of the last `antithesis_sdk` line
+/// before it, since several SDKs can write to the same output. Its location
+/// and `display_type` share their strings with the earlier assertions of the
+/// same input, which keeps the many hits of large logs small.
+pub fn parse_lines<R: BufRead>(reader: R) -> ParsedLines<R> {
+    ParsedLines { reader, line: 0, offset: 0, buf: Vec::new(), read_line: 0, read_offset: 0, pending: VecDeque::new(), read_error: None, parallel: false, strict: false, sdk: None, context_events: 0, recent_events: VecDeque::new(), events: EventFilter::default(), timestamp_field: None, namespace: None, span: Span::default(), strings: Interner::default() }
+}
+
+// How many lines are parsed together with ParsedLines::parallel
+const PARALLEL_BATCH: usize = 4096;
+
+// A line as read, before it is parsed
+struct RawLine {
+    line: usize,
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+// What a line parsed into, None for an empty line
+type ParsedLine = Result<Option<(SDKInput, Option<Timestamp>)>>;
+
+/// The iterator returned by [`parse_lines`].
+pub struct ParsedLines<R> {
+    reader: R,
+    // The line last returned
+    line: usize,
+    offset: u64,
+    buf: Vec<u8>,
+    // How far the reader got, which is ahead of the above while lines are pending
+    read_line: usize,
+    read_offset: u64,
+    pending: VecDeque<(RawLine, ParsedLine)>,
+    // Held back until the lines read before it were returned
+    read_error: Option<std::io::Error>,
+    parallel: bool,
+    strict: bool,
+    sdk: Option<AntithesisSdk>,
+    context_events: usize,
+    recent_events: VecDeque<ContextEvent>,
+    events: EventFilter,
+    timestamp_field: Option<String>,
+    namespace: Option<String>,
+    span: Span,
+    strings: Interner,
+}
+
+impl<R> ParsedLines<R> {
+    /// Read lines ahead in batches and parse each batch on all cores, still
+    /// returning them in order. Only for input that is read to its end:
+    /// a batch is filled before any of it is returned, so a slow stream
+    /// (see [`Follow`](crate::input::Follow)) would be held back.
+    ///
+    /// When the reader has a whole batch in its buffer, as it has for a
+    /// file opened with [`open_mapped`](crate::input::open_mapped), the
+    /// lines are parsed there instead of being copied out one by one.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Parse each line with [`parse_line_strict`] instead of [`parse_line`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Keep the last `n` user events, and give them to each assertion hit
+    /// that may fail it (see [`AntithesisAssert::may_fail`](crate::AntithesisAssert::may_fail)).
+    pub fn context_events(mut self, n: usize) -> Self {
+        self.context_events = n;
+        self
+    }
+
+    /// Skip the user events that `filter` doesn't keep, as if they were not
+    /// in the input. Its sampling starts over with each input.
+    pub fn events(mut self, filter: EventFilter) -> Self {
+        self.events = filter;
+        self
+    }
+
+    /// Take this top-level field of each line as the time it was written,
+    /// parsing the rest of the line as usual. Assertions keep their
+    /// [`timestamp`](crate::AntithesisAssert::timestamp).
+    pub fn timestamp_field(mut self, field: Option<String>) -> Self {
+        self.timestamp_field = field;
+        self
+    }
+
+    /// Prefix the id of each assertion with `namespace:`, e.g. the input it
+    /// was read from, so assertions of different inputs that share an id
+    /// are evaluated apart.
+    pub fn namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    /// The earliest and latest timestamps read so far.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// The number of the line last read, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The text of the line last read, without its line ending, or None
+    /// when it is not valid UTF-8.
+    ///
+    /// Empty for lines that were parsed in place, see
+    /// [`parallel`](Self::parallel).
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.buf).ok().map(str::trim_end)
+    }
+}
+
+impl<R: BufRead> Iterator for ParsedLines<R> {
+    type Item = Result<SDKInput>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pending.is_empty() {
+                if let Err(e) = self.read_batch() {
+                    return Some(Err(e.into()));
+                }
+            }
+            let (raw, parsed) = self.pending.pop_front()?;
+            self.line = raw.line;
+            self.offset = raw.offset;
+            self.buf = raw.buf;
+
+            let (mut x, timestamp) = match parsed {
+                Ok(Some(x)) => x,
+                Ok(None) => continue,
+                Err(e) => return Some(Err(self.error(e).into())),
+            };
+            if let Some(timestamp) = &timestamp {
+                self.span.record(timestamp);
+            }
+            if let SDKInput::SendEvent{ event_name, .. } = &x {
+                if !self.events.keeps(event_name) {
+                    continue;
+                }
+            }
+            match &mut x {
+                SDKInput::AntithesisSdk(sdk) => self.sdk = Some(sdk.clone()),
+                SDKInput::AntithesisAssert(assert) => {
+                    self.strings.intern_location(&mut assert.location);
+                    self.strings.intern(&mut assert.display_type);
+                    assert.sdk.clone_from(&self.sdk);
+                    assert.timestamp = timestamp;
+                    assert.line = self.line;
+                    if let Some(namespace) = &self.namespace {
+                        assert.id = format!("{}:{}", namespace, assert.group_id());
+                    }
+                    if assert.may_fail() {
+                        assert.context_events = self.recent_events.iter().cloned().collect();
+                    }
+                },
+                SDKInput::SendEvent{ event_name, details } if self.context_events > 0 => {
+                    if self.recent_events.len() == self.context_events {
+                        self.recent_events.pop_front();
+                    }
+                    self.recent_events.push_back(ContextEvent { line: self.line, event_name: event_name.clone(), details: details.clone() });
+                },
+                _ => {},
+            }
+            return Some(Ok(x));
+        }
+    }
+}
+
+impl<R: BufRead> ParsedLines<R> {
+    // Read and parse the next line, or the next batch of them when parallel.
+    // Nothing is pending afterwards at the end of the input, which may grow
+    // and be read again later
+    fn read_batch(&mut self) -> std::io::Result<()> {
+        if let Some(e) = self.read_error.take() {
+            return Err(e);
+        }
+        if self.parallel && self.read_batch_in_place()? {
+            return Ok(());
+        }
+        let size = if self.parallel { PARALLEL_BATCH } else { 1 };
+        let mut batch = Vec::new();
+        while batch.len() < size {
+            let mut buf = Vec::new();
+            match self.reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.read_line += 1;
+                    batch.push(RawLine { line: self.read_line, offset: self.read_offset, buf });
+                    self.read_offset += n as u64;
+                },
+                Err(e) if batch.is_empty() => return Err(e),
+                Err(e) => {
+                    self.read_error = Some(e);
+                    break;
+                },
+            }
+        }
+
+        let strict = self.strict;
+        let field = self.timestamp_field.as_deref();
+        let parsed: Vec<ParsedLine> = if self.parallel {
+            batch.par_iter().map(|x| parse_raw(&x.buf, strict, field)).collect()
+        } else {
+            batch.iter().map(|x| parse_raw(&x.buf, strict, field)).collect()
+        };
+        self.pending.extend(batch.into_iter().zip(parsed));
+        Ok(())
+    }
+
+    // Parse a whole batch where it is in the buffer of the reader, if the
+    // buffer holds that many lines, e.g. all of a mapped file (see
+    // input::open_mapped). Only the lines that could not be parsed are
+    // copied, for their ParseError
+    fn read_batch_in_place(&mut self) -> std::io::Result<bool> {
+        let available = self.reader.fill_buf()?;
+        let mut lines = Vec::with_capacity(PARALLEL_BATCH);
+        let mut rest = available;
+        while lines.len() < PARALLEL_BATCH {
+            let Some(end) = rest.iter().position(|x| *x == b'\n') else {
+                return Ok(false);
+            };
+            let (line, after) = rest.split_at(end + 1);
+            lines.push(line);
+            rest = after;
+        }
+
+        let strict = self.strict;
+        let field = self.timestamp_field.as_deref();
+        let parsed: Vec<ParsedLine> = lines.par_iter().map(|x| parse_raw(x, strict, field)).collect();
+        for (line, parsed) in lines.iter().zip(parsed) {
+            self.read_line += 1;
+            let buf = if parsed.is_err() { line.to_vec() } else { Vec::new() };
+            self.pending.push_back((RawLine { line: self.read_line, offset: self.read_offset, buf }, parsed));
+            self.read_offset += line.len() as u64;
+        }
+        let read = available.len() - rest.len();
+        self.reader.consume(read);
+        Ok(true)
+    }
+}
+
+// The same few files, classes, functions and types are repeated on every
+// hit, so equal strings of an input share one allocation instead of one each
+#[derive(Default)]
+struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    fn intern(&mut self, string: &mut Arc<str>) {
+        match self.strings.get(string) {
+            Some(x) => *string = x.clone(),
+            None => {
+                self.strings.insert(string.clone());
+            },
+        }
+    }
+
+    fn intern_location(&mut self, location: &mut Location) {
+        for string in [&mut location.file, &mut location.class, &mut location.function].into_iter().flatten() {
+            self.intern(string);
+        }
+    }
+}
+
+// The part of parsing a line that doesn't depend on the lines before it
+fn parse_raw(buf: &[u8], strict: bool, field: Option<&str>) -> ParsedLine {
+    let line = std::str::from_utf8(buf)?.trim_end();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let (line, timestamp) = match field {
+        Some(field) => split_timestamp(line, field),
+        None => (Cow::Borrowed(line), None),
+    };
+    let parse = if strict { parse_line_strict } else { parse_line };
+    parse(&line).map(|x| Some((x, timestamp)))
+}
+
+// Take `field` out of `line`, leaving the rest to be parsed. Lines that are
+// not JSON objects are left for the parser to reject
+fn split_timestamp<'a>(line: &'a str, field: &str) -> (Cow<'a, str>, Option<Timestamp>) {
+    let mut value: Value = match serde_json::from_str(line) {
+        Ok(x) => x,
+        Err(_) => return (Cow::Borrowed(line), None),
+    };
+    match value.as_object_mut().and_then(|x| x.remove(field)) {
+        Some(timestamp) => (Cow::Owned(value.to_string()), Timestamp::parse(timestamp)),
+        None => (Cow::Borrowed(line), None),
+    }
+}
+
+impl<R> ParsedLines<R> {
+    fn error(&self, e: anyhow::Error) -> ParseError {
+        let line = String::from_utf8_lossy(&self.buf);
+        ParseError {
+            file: None,
+            line: self.line,
+            offset: self.offset,
+            error: e.to_string(),
+            snippet: line.trim_end().chars().take(SNIPPET_CHARS).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    // Lines of every kind, over several parallel batches, some ending in
+    // CRLF and the last one without a line ending
+    fn input() -> Vec<u8> {
+        let mut input = String::from("{\"antithesis_sdk\": {\"language\": \"rust\", \"version\": \"1\"}}\n");
+        for i in 0..3 * PARALLEL_BATCH + 17 {
+            let line = match i % 7 {
+                0 => format!("{{\"ts\": {}, \"event{}\": {{\"n\": {}}}}}", i, i % 3, i),
+                1 => String::new(),
+                2 if i % 5 == 0 => format!("not json {}", i),
+                3 => format!("{{\"ts\": {}, \"antithesis_assert\": {{\"id\": \"\", \"message\": \"m\", \"assert_type\": \"sometimes\", \"display_type\": \"Sometimes\", \"condition\": true, \"hit\": true, \"must_hit\": true, \"location\": {{\"file\": \"src/b.rs\", \"function\": \"f\", \"begin_line\": {}}}, \"details\": null}}}}", i, i % 4),
+                _ => format!("{{\"ts\": {}, \"antithesis_assert\": {{\"id\": \"a{}\", \"message\": \"m\", \"assert_type\": \"always\", \"display_type\": \"Always\", \"condition\": {}, \"hit\": true, \"must_hit\": true, \"location\": {{\"file\": \"src/a.rs\"}}, \"details\": {{\"i\": {}}}}}}}", i, i % 11, i % 13 != 0, i),
+            };
+            input.push_str(&line);
+            input.push_str(if i % 2 == 0 { "\r\n" } else { "\n" });
+        }
+        input.push_str("{\"antithesis_setup\": {\"status\": \"complete\", \"details\": null}}");
+        input.into_bytes()
+    }
+
+    // What each line parsed into, or the line, offset and snippet of its error
+    fn parse_all<R: BufRead>(lines: ParsedLines<R>) -> Vec<String> {
+        let mut lines = lines.context_events(2).timestamp_field(Some("ts".to_string()));
+        let mut parsed = Vec::new();
+        while let Some(x) = lines.next() {
+            parsed.push(match x {
+                Ok(SDKInput::AntithesisAssert(x)) => format!("{} {:?} {:?} {:?}", lines.line(), x, x.timestamp, x.context_events),
+                Ok(x) => format!("{} {:?}", lines.line(), x),
+                Err(e) => {
+                    let e: ParseError = e.downcast().unwrap();
+                    format!("error {} {} {:?} {}", e.line, e.offset, e.snippet, e.error)
+                },
+            });
+        }
+        parsed
+    }
+
+    #[test]
+    fn parallel_parses_like_serial() {
+        let input = input();
+        let serial = parse_all(parse_lines(BufReader::new(&input[..])));
+        assert!(serial.len() > 3 * PARALLEL_BATCH / 2);
+        assert!(serial.iter().any(|x| x.starts_with("error ")));
+
+        // Copied out of a small buffer
+        let parallel = parse_all(parse_lines(BufReader::with_capacity(64, &input[..])).parallel(true));
+        assert_eq!(serial, parallel);
+
+        // Parsed in place, the slice is all in the buffer
+        let in_place = parse_all(parse_lines(&input[..]).parallel(true));
+        assert_eq!(serial, in_place);
+    }
+
+    #[test]
+    fn mapped_parses_like_read() {
+        let path = std::env::temp_dir().join(format!("crunch-mapped-{}.jsonl", std::process::id()));
+        std::fs::write(&path, input()).unwrap();
+        let path = path.to_str().unwrap();
+        let read = parse_all(parse_lines(crate::input::open(path).unwrap()));
+        let mapped = parse_all(parse_lines(crate::input::open_mapped(path).unwrap()));
+        let mapped_parallel = parse_all(parse_lines(crate::input::open_mapped(path).unwrap()).parallel(true));
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(read, mapped);
+        assert_eq!(read, mapped_parallel);
+    }
+
+    #[test]
+    fn error_line_numbers() {
+        let input = b"{\"e\": {}}\r\n\nnot json\r\n{\"e\": {}}\nalso not";
+        for parallel in [false, true] {
+            let errors: Vec<(usize, u64, String)> = parse_lines(&input[..]).parallel(parallel)
+                .filter_map(|x| x.err())
+                .map(|e| {
+                    let e: ParseError = e.downcast().unwrap();
+                    (e.line, e.offset, e.snippet)
+                })
+                .collect();
+            assert_eq!(errors, vec![(3, 12, "not json".to_string()), (5, 32, "also not".to_string())]);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{ Context, Result };
+use regex::Regex;
+use serde::{ Deserialize };
+use std::fs;
+use std::path::Path;
+
+use crate::evaluate::EvaluatedAssertion;
+
+/// Assertions that are known to fail, whose failures are reported as
+/// expected instead of counting against the run.
+///
+/// Read from TOML such as:
+///
+/// ```toml
+/// ids = ["queue never drops a message"]
+/// regexes = ["^flaky: "]
+/// ```
+#[derive(Deserialize, Default, Debug)]
+#[serde(try_from = "QuarantineFile")]
+pub struct Quarantine {
+    ids: Vec<String>,
+    regexes: Vec<Regex>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuarantineFile {
+    #[serde(default)]
+    ids: Vec<String>,
+    #[serde(default)]
+    regexes: Vec<String>,
+}
+
+impl TryFrom<QuarantineFile> for Quarantine {
+    type Error = regex::Error;
+
+    fn try_from(file: QuarantineFile) -> Result<Self, Self::Error> {
+        let regexes = file.regexes.iter().map(|x| Regex::new(x)).collect::<Result<_, _>>()?;
+        Ok(Self { ids: file.ids, regexes })
+    }
+}
+
+impl Quarantine {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("unable to parse {}", path.display()))
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.iter().any(|x| x == id) || self.regexes.iter().any(|x| x.is_match(id))
+    }
+
+    /// Mark the quarantined failures as expected, and return the quarantined
+    /// assertions that passed after all.
+    pub fn apply<'a>(&self, assertions: &'a mut [EvaluatedAssertion]) -> Vec<&'a EvaluatedAssertion> {
+        let mut unexpected_passes = Vec::new();
+        for evaled in assertions.iter_mut() {
+            if !self.contains(&evaled.id) {
+                continue;
+            }
+            if evaled.passed {
+                unexpected_passes.push(&*evaled);
+            } else {
+                evaled.expected_failure = true;
+            }
+        }
+        unexpected_passes
+    }
+}
@@ -0,0 +1,482 @@
+use anyhow::{ Result, bail };
+use serde::de::DeserializeOwned;
+use serde::{ Serialize };
+use serde_json::{ Value };
+use std::cmp::{ Ordering, Reverse };
+use std::collections::BTreeMap;
+use std::process::{ Command, Stdio };
+use std::str::FromStr;
+
+use crate::evaluate::{ EvaluatedAssertion, FirstFailure };
+use crate::events::{ EventSummary, TimelineEvent };
+use crate::guidance::GuidanceSummary;
+use crate::parse::ParseError;
+use crate::sdk::{ AntithesisSdk, AntithesisSetup, AssertType, SDKInput };
+use crate::timestamp::{ Span, Timestamp };
+
+/// The version of the [`Report`] document, raised whenever a field is
+/// removed or changes meaning (see `crunch schema`).
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Counts over all evaluated assertions.
+#[derive(Serialize, Debug, Default)]
+pub struct Summary {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Quarantined failures, which are not counted in `failed`.
+    pub expected_failures: usize,
+    /// Assertions that were registered but never evaluated during the run.
+    pub never_hit: usize,
+    /// Assertions of an unknown type, which are neither passed nor failed.
+    pub unevaluated: usize,
+    /// `passed / total`, or `None` when there are no assertions.
+    pub pass_rate: Option<f64>,
+    pub by_type: BTreeMap<AssertType, TypeSummary>,
+    /// Counts by the language of the SDK that wrote each assertion, leaving
+    /// out assertions that no `antithesis_sdk` line came before.
+    pub by_language: BTreeMap<String, TypeSummary>,
+    /// Counts by source file, worst first: most failures, then most never hit.
+    pub by_file: Vec<FileSummary>,
+    /// The earliest timestamp read, with `--timestamp-field`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<Timestamp>,
+    /// The latest timestamp read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<Timestamp>,
+    /// Seconds from `started_at` to `ended_at`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<f64>,
+    /// The earliest [`first_failure`](EvaluatedAssertion::first_failure) of
+    /// the failures that count (not quarantined).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_failure: Option<FirstFailure>,
+}
+
+/// Counts over the evaluated assertions of one source file.
+#[derive(Serialize, Debug)]
+pub struct FileSummary {
+    /// Empty for assertions whose location has no file.
+    pub file: String,
+    #[serde(flatten)]
+    pub counts: TypeSummary,
+}
+
+/// Counts over the evaluated assertions of one [`AssertType`] or language.
+#[derive(Serialize, Debug, Default)]
+pub struct TypeSummary {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub expected_failures: usize,
+    pub never_hit: usize,
+    pub unevaluated: usize,
+}
+
+impl Summary {
+    pub fn new(assertions: &[EvaluatedAssertion]) -> Self {
+        let mut all = TypeSummary::default();
+        let mut by_type: BTreeMap<AssertType, TypeSummary> = BTreeMap::new();
+        let mut by_language: BTreeMap<String, TypeSummary> = BTreeMap::new();
+        let mut by_file: BTreeMap<&str, TypeSummary> = BTreeMap::new();
+        for evaled in assertions {
+            by_file.entry(evaled.location.file()).or_default().count(evaled);
+            all.count(evaled);
+            by_type.entry(evaled.assert_type.clone()).or_default().count(evaled);
+            if let Some(sdk) = &evaled.sdk {
+                by_language.entry(sdk.language.clone()).or_default().count(evaled);
+            }
+        }
+        Self {
+            total: all.total,
+            passed: all.passed,
+            failed: all.failed,
+            expected_failures: all.expected_failures,
+            never_hit: all.never_hit,
+            unevaluated: all.unevaluated,
+            pass_rate: (all.total > 0).then(|| all.passed as f64 / all.total as f64),
+            by_type,
+            by_language,
+            by_file: worst_first(by_file),
+            started_at: None,
+            ended_at: None,
+            duration_seconds: None,
+            first_failure: first_failure(assertions),
+        }
+    }
+}
+
+fn worst_first(counts: BTreeMap<&str, TypeSummary>) -> Vec<FileSummary> {
+    let mut by_file: Vec<FileSummary> = counts.into_iter()
+        .map(|(file, counts)| FileSummary { file: file.to_string(), counts })
+        .collect();
+    // Stable, so ties stay in file order
+    by_file.sort_by_key(|x| Reverse((x.counts.failed, x.counts.never_hit)));
+    by_file
+}
+
+fn first_failure(assertions: &[EvaluatedAssertion]) -> Option<FirstFailure> {
+    assertions.iter()
+        .filter(|x| !x.expected_failure)
+        .filter_map(|x| x.first_failure.as_ref())
+        .fold(None, |first: Option<&FirstFailure>, x| match first {
+            Some(first) if !x.is_before(first) => Some(first),
+            _ => Some(x),
+        })
+        .cloned()
+}
+
+impl TypeSummary {
+    fn count(&mut self, evaled: &EvaluatedAssertion) {
+        self.total += 1;
+        if evaled.passed {
+            self.passed += 1;
+        } else if evaled.unevaluated {
+            self.unevaluated += 1;
+        } else if evaled.expected_failure {
+            self.expected_failures += 1;
+        } else {
+            self.failed += 1;
+        }
+        if !evaled.hit {
+            self.never_hit += 1;
+        }
+    }
+}
+
+/// What the input said about the SDK and the setup of the workload, and
+/// what the run was tagged with.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RunInfo {
+    /// The SDKs that wrote the input, in the order they were first seen.
+    pub sdks: Vec<AntithesisSdk>,
+    /// The last setup status reported by the workload.
+    pub setup: Option<AntithesisSetup>,
+    /// Names this run, e.g. after the build it tested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// The commit checked out where crunch ran, if in a git repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+}
+
+/// The commit of a git checkout.
+#[derive(Serialize, Clone, Debug)]
+pub struct GitInfo {
+    pub sha: String,
+    /// `None` for a detached HEAD.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl GitInfo {
+    /// The commit of the repository around the working directory, `None`
+    /// outside of one or when git isn't installed.
+    pub fn detect() -> Option<Self> {
+        let git = |args: &[&str]| -> Option<String> {
+            let output = Command::new("git").args(args).stderr(Stdio::null()).output().ok()?;
+            let text = String::from_utf8(output.stdout).ok()?;
+            (output.status.success() && !text.trim().is_empty()).then(|| text.trim().to_string())
+        };
+        let sha = git(&["rev-parse", "HEAD"])?;
+        let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|x| x != "HEAD");
+        Some(GitInfo { sha, branch })
+    }
+}
+
+impl RunInfo {
+    /// Keep `input` if it is an `antithesis_sdk` or `antithesis_setup` line.
+    pub fn record(&mut self, input: &SDKInput) {
+        match input {
+            SDKInput::AntithesisSdk(x) if !self.sdks.contains(x) => self.sdks.push(x.clone()),
+            SDKInput::AntithesisSetup(x) => self.setup = Some(x.clone()),
+            _ => {},
+        }
+    }
+
+    /// Add the SDKs and setup status `other` recorded, e.g. from a later input.
+    pub fn merge(&mut self, other: RunInfo) {
+        for sdk in other.sdks {
+            if !self.sdks.contains(&sdk) {
+                self.sdks.push(sdk);
+            }
+        }
+        if other.setup.is_some() {
+            self.setup = other.setup;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sdks.is_empty() && self.setup.is_none() && self.run_id.is_none() && self.labels.is_empty() && self.git.is_none()
+    }
+}
+
+/// Everything learned from one run over the SDK output.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub schema_version: u32,
+    pub run_info: RunInfo,
+    pub summary: Summary,
+    pub assertions: Vec<EvaluatedAssertion>,
+    /// What was observed for each `antithesis_guidance` id, by id.
+    pub guidance: Vec<GuidanceSummary>,
+    /// The user events by name.
+    pub events: Vec<EventSummary>,
+    /// Every user event, with `--events-timeline`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timeline: Vec<TimelineEvent>,
+    pub parse_errors: Vec<ParseError>,
+}
+
+impl Report {
+    /// The assertions are ordered by id, then location, so the same input
+    /// always gives the same report.
+    pub fn new(mut assertions: Vec<EvaluatedAssertion>, parse_errors: Vec<ParseError>) -> Self {
+        assertions.sort_by(by_id);
+        Self {
+            schema_version: SCHEMA_VERSION,
+            run_info: RunInfo::default(),
+            summary: Summary::new(&assertions),
+            assertions,
+            guidance: Vec::new(),
+            events: Vec::new(),
+            timeline: Vec::new(),
+            parse_errors,
+        }
+    }
+
+    /// Record when the run started and ended, from the timestamps read, and
+    /// how long into it each first failure came.
+    pub fn set_span(&mut self, span: Span) {
+        if let Some(start) = &span.first {
+            for failure in self.assertions.iter_mut().filter_map(|x| x.first_failure.as_mut()) {
+                failure.after_seconds = failure.at.as_ref().map(|x| x.seconds - start.seconds);
+            }
+        }
+        let summary = &mut self.summary;
+        summary.duration_seconds = span.duration();
+        summary.started_at = span.first;
+        summary.ended_at = span.last;
+        summary.first_failure = first_failure(&self.assertions);
+    }
+
+    /// Set the guidance of the report, and of each assertion with the same id.
+    pub fn set_guidance(&mut self, guidance: Vec<GuidanceSummary>) {
+        for evaled in &mut self.assertions {
+            evaled.guidance = guidance.iter().find(|x| x.id == evaled.id).cloned();
+        }
+        self.guidance = guidance;
+    }
+
+    /// Order the assertions by `key`, ties are broken by id then location.
+    pub fn sort(&mut self, key: SortKey, reverse: bool) {
+        self.assertions.sort_by(|a, b| {
+            let ordering = match key {
+                SortKey::Id => Ordering::Equal,
+                SortKey::File => (&a.location.file, a.location.begin_line).cmp(&(&b.location.file, b.location.begin_line)),
+                // Failures first
+                SortKey::Status => a.passed.cmp(&b.passed),
+                SortKey::Type => a.assert_type.cmp(&b.assert_type),
+            };
+            ordering.then_with(|| by_id(a, b))
+        });
+        if reverse {
+            self.assertions.reverse();
+        }
+    }
+
+    /// The `n` failed assertions with the most counterexample hits, most
+    /// first, leaving out expected failures.
+    pub fn top_failing(&self, n: usize) -> Vec<&EvaluatedAssertion> {
+        let mut failing: Vec<&EvaluatedAssertion> = self.assertions.iter()
+            .filter(|x| !x.expected_failure && x.counterexample_hits() > 0)
+            .collect();
+        failing.sort_by_key(|x| Reverse(x.counterexample_hits()));
+        failing.truncate(n);
+        failing
+    }
+
+    /// The assertions grouped by `key`, in report order within each group.
+    pub fn grouped(&self, key: GroupBy) -> BTreeMap<&str, Vec<&EvaluatedAssertion>> {
+        let mut groups: BTreeMap<&str, Vec<&EvaluatedAssertion>> = BTreeMap::new();
+        for evaled in &self.assertions {
+            groups.entry(key.of(evaled)).or_default().push(evaled);
+        }
+        groups
+    }
+}
+
+fn by_id(a: &EvaluatedAssertion, b: &EvaluatedAssertion) -> Ordering {
+    fn key(x: &EvaluatedAssertion) -> (&str, &str, Option<i32>, Option<i32>, &str, &str) {
+        let location = &x.location;
+        (&x.id, location.file(), location.begin_line, location.begin_column, location.class(), location.function())
+    }
+    key(a).cmp(&key(b))
+}
+
+/// What to nest the assertions of a [`Report`] under when rendering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    File,
+    Class,
+    Function,
+    DisplayType,
+}
+
+impl GroupBy {
+    /// The group `evaled` belongs to.
+    pub fn of(self, evaled: &EvaluatedAssertion) -> &str {
+        match self {
+            GroupBy::File => evaled.location.file(),
+            GroupBy::Class => evaled.location.class(),
+            GroupBy::Function => evaled.location.function(),
+            GroupBy::DisplayType => &evaled.display_type,
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(GroupBy::File),
+            "class" => Ok(GroupBy::Class),
+            "function" => Ok(GroupBy::Function),
+            "display-type" => Ok(GroupBy::DisplayType),
+            _ => Err(format!("unknown grouping {} (expected file, class, function or display-type)", s)),
+        }
+    }
+}
+
+/// What to order the assertions of a [`Report`] by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Id,
+    /// Source file, then line.
+    File,
+    /// Failed before passed.
+    Status,
+    Type,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(SortKey::Id),
+            "file" => Ok(SortKey::File),
+            "status" => Ok(SortKey::Status),
+            "type" => Ok(SortKey::Type),
+            _ => Err(format!("unknown sort key {} (expected id, file, status or type)", s)),
+        }
+    }
+}
+
+/// Whether `head`, the start of a file with at least its first two non-blank
+/// lines, is a report rather than SDK output. Blank lines are skipped, and
+/// the `json` report is pretty-printed, its first field on the second line.
+pub fn is_report_start(head: &str) -> bool {
+    let mut lines = head.lines().map(str::trim).filter(|x| !x.is_empty());
+    match lines.next() {
+        Some("{") => lines.next().is_some_and(|x| x.starts_with("\"schema_version\"") || x.starts_with("\"run_info\"")),
+        Some(line) => is_report_line(line),
+        None => false,
+    }
+}
+
+/// Whether `line` is the first line of a `jsonl` report, or a whole `json`
+/// report on one line.
+pub fn is_report_line(line: &str) -> bool {
+    match serde_json::from_str::<Value>(line) {
+        Ok(Value::Object(x)) => x.contains_key("schema_version") || x.contains_key("run_info") || (x.contains_key("id") && x.contains_key("status")),
+        _ => false,
+    }
+}
+
+// The lines of a `jsonl` report besides the assertions, each an object with
+// just one of these
+const REPORT_SECTIONS: &[&str] = &["run_info", "guidance", "events", "timeline", "parse_errors"];
+
+/// The assertions of a `json` report, or the assertion lines of a `jsonl`
+/// report, as [`EvaluatedAssertion`]s or anything else they deserialize into.
+///
+/// Fails on any line that is not part of a report, e.g. on SDK output.
+pub fn read_assertions<T: DeserializeOwned>(text: &str) -> Result<Vec<T>> {
+    if let Ok(Value::Object(mut report)) = serde_json::from_str::<Value>(text) {
+        if let Some(assertions) = report.remove("assertions") {
+            return Ok(serde_json::from_value(assertions)?);
+        }
+    }
+    let mut assertions = Vec::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, x)| !x.trim().is_empty()) {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(x) => x,
+            Err(e) => bail!("line {}: not a crunch report: {}", i + 1, e),
+        };
+        match &value {
+            Value::Object(x) if x.contains_key("id") => assertions.push(serde_json::from_value(value)?),
+            Value::Object(x) if x.len() == 1 && x.keys().all(|x| REPORT_SECTIONS.contains(&x.as_str())) => {},
+            _ => bail!("line {}: not a crunch report, the line is neither an assertion nor one of {}", i + 1, REPORT_SECTIONS.join(", ")),
+        }
+    }
+    Ok(assertions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ group_asserts, parse_line };
+
+    const SDK_OUTPUT: &str = r#"{"antithesis_assert": {"id": "a", "message": "a", "assert_type": "always", "display_type": "Always", "condition": false, "hit": true, "must_hit": true, "location": {"file": "src/a.rs"}, "details": null}}
+{"antithesis_assert": {"id": "b", "message": "b", "assert_type": "sometimes", "display_type": "Sometimes", "condition": true, "hit": true, "must_hit": true, "location": {"file": "src/b.rs"}, "details": null}}
+"#;
+
+    fn report() -> Report {
+        let parsed = SDK_OUTPUT.lines().map(|x| parse_line(x).unwrap());
+        let assertions = group_asserts(parsed).into_values().map(EvaluatedAssertion::new).collect();
+        Report::new(assertions, Vec::new())
+    }
+
+    fn jsonl(report: &Report) -> String {
+        let mut out = Vec::new();
+        crate::output::jsonl::write(report, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn reports_are_told_from_sdk_output() {
+        let report = report();
+        assert!(is_report_start(&serde_json::to_string_pretty(&report).unwrap()));
+        assert!(is_report_start(&serde_json::to_string(&report).unwrap()));
+        assert!(is_report_start(&jsonl(&report)));
+        assert!(is_report_start(&format!("\n\n{}", jsonl(&report))));
+
+        assert!(!is_report_start(SDK_OUTPUT));
+        assert!(!is_report_start(""));
+        // A blank line or a preamble before the SDK output is still SDK output
+        assert!(!is_report_start(&format!("\n{}", SDK_OUTPUT)));
+        assert!(!is_report_start(&format!("starting the workload\n{}", SDK_OUTPUT)));
+        assert!(!is_report_start("{\n\"antithesis_setup\": {}\n}"));
+    }
+
+    #[test]
+    fn assertions_are_read_back() {
+        let report = report();
+        for text in [serde_json::to_string_pretty(&report).unwrap(), jsonl(&report)] {
+            let mut ids: Vec<String> = read_assertions::<EvaluatedAssertion>(&text).unwrap().into_iter().map(|x| x.id).collect();
+            ids.sort();
+            assert_eq!(ids, ["a", "b"]);
+        }
+    }
+
+    #[test]
+    fn sdk_output_is_not_read_as_a_report() {
+        assert!(read_assertions::<EvaluatedAssertion>(SDK_OUTPUT).is_err());
+        let mixed = format!("{}{}", jsonl(&report()), SDK_OUTPUT);
+        assert!(read_assertions::<EvaluatedAssertion>(&mixed).is_err());
+    }
+}
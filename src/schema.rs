@@ -0,0 +1,62 @@
+// JSON Schemas of the SDK output, for checking lines more closely than serde
+// does, and of the reports, for consumers to validate and pin against.
+
+use anyhow::{ anyhow, Result };
+use serde_json::{ Value };
+use std::fmt;
+
+/// The JSON Schema (draft 2020-12) of a line of SDK output, as accepted by
+/// [`parse_line_strict`](crate::parse_line_strict).
+pub const SDK_INPUT: &str = include_str!("../schema/sdk-input.schema.json");
+
+/// The JSON Schema of the `json` report, for [`SCHEMA_VERSION`](crate::SCHEMA_VERSION).
+pub const REPORT: &str = include_str!("../schema/report.schema.json");
+
+/// The JSON Schema of one [`EvaluatedAssertion`](crate::EvaluatedAssertion),
+/// as written on each line of the `jsonl` report.
+///
+/// This is the `assertion` definition of [`REPORT`], made the root.
+pub fn assertion() -> Result<Value> {
+    let mut schema: Value = serde_json::from_str(REPORT)?;
+    let root = schema.as_object_mut().ok_or_else(|| anyhow!("the report schema is not an object"))?;
+    root.retain(|key, _| key == "$schema" || key == "$defs");
+    root.insert("title".to_string(), Value::from("crunch evaluated assertion"));
+    root.insert("$ref".to_string(), Value::from("#/$defs/assertion"));
+    Ok(schema)
+}
+
+/// A field of a line that doesn't match the schema.
+#[derive(Debug)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value, empty for the line itself.
+    pub path: String,
+    pub error: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        write!(f, "{}: {}", path, self.error)
+    }
+}
+
+/// Checks parsed lines against [`SDK_INPUT`].
+pub struct InputSchema {
+    validator: jsonschema::Validator,
+}
+
+impl InputSchema {
+    pub fn new() -> Result<Self> {
+        let schema: Value = serde_json::from_str(SDK_INPUT)?;
+        let validator = jsonschema::validator_for(&schema)
+            .map_err(|e| anyhow!("invalid SDK input schema: {}", e))?;
+        Ok(InputSchema { validator })
+    }
+
+    /// Every place where `line` doesn't match the schema, empty when it does.
+    pub fn check(&self, line: &Value) -> Vec<SchemaError> {
+        self.validator.iter_errors(line)
+            .map(|e| SchemaError { path: e.instance_path().to_string(), error: e.to_string() })
+            .collect()
+    }
+}
@@ -0,0 +1,202 @@
+// The shapes of the JSON lines written by the Antithesis SDKs.
+
+use serde::{ Deserialize, Serialize };
+use serde_json::{ Value };
+use std::borrow::Cow;
+use std::fmt;
+use crate::events::ContextEvent;
+use crate::timestamp::Timestamp;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Identifies the SDK that produced the output (`antithesis_sdk` lines).
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AntithesisSdk {
+    pub language: String, 
+    pub version: String 
+}
+
+/// Setup status reported by the workload (`antithesis_setup` lines).
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AntithesisSetup {
+    pub status: String,
+    pub details: Value,
+}
+
+/// Where in the instrumented source an assertion lives.
+///
+/// Not every SDK knows every field (Go has no classes, Python no columns),
+/// so any of them may be missing; missing fields are left out when written.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Location {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub begin_column: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub begin_line: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<Arc<str>>,
+}
+
+impl Location {
+    /// The file, or `""` when it is not known.
+    pub fn file(&self) -> &str {
+        self.file.as_deref().unwrap_or_default()
+    }
+
+    /// The class, or `""` when it is not known.
+    pub fn class(&self) -> &str {
+        self.class.as_deref().unwrap_or_default()
+    }
+
+    /// The function, or `""` when it is not known.
+    pub fn function(&self) -> &str {
+        self.function.as_deref().unwrap_or_default()
+    }
+}
+
+/// `file:line`, leaving out what is not known.
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.as_deref().unwrap_or("(unknown)"))?;
+        if let Some(line) = self.begin_line {
+            write!(f, ":{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// One `antithesis_assert` line.
+///
+/// Entries with `hit == false` are catalog entries, registered by the SDK
+/// for every assertion it knows about. Entries with `hit == true` are
+/// emitted each time the assertion is actually evaluated.
+#[derive(Deserialize, Clone, Debug)]
+pub struct AntithesisAssert {
+    pub assert_type: AssertType,
+    pub condition: bool,
+    pub display_type: Arc<str>,
+    pub hit: bool,
+    pub must_hit: bool,
+    pub id: String,
+    pub message: String,
+    pub location: Location,
+    pub details: Value,
+    /// The SDK of the last `antithesis_sdk` line before this one, not part of
+    /// the line itself (see [`ParsedLines`](crate::ParsedLines)).
+    #[serde(skip)]
+    pub sdk: Option<AntithesisSdk>,
+    /// The user events read just before a hit that may fail the assertion,
+    /// see [`ParsedLines::context_events`](crate::ParsedLines::context_events).
+    #[serde(skip)]
+    pub context_events: Vec<ContextEvent>,
+    /// When the line was written, see
+    /// [`ParsedLines::timestamp_field`](crate::ParsedLines::timestamp_field).
+    #[serde(skip)]
+    pub timestamp: Option<Timestamp>,
+    /// The line this was read from, within its input (see
+    /// [`ParsedLines::line`](crate::ParsedLines::line)).
+    #[serde(skip)]
+    pub line: usize,
+}
+
+impl AntithesisAssert {
+    /// Whether this line alone can fail the assertion: a hit with a false
+    /// condition, or any hit of an Unreachable assertion.
+    pub fn may_fail(&self) -> bool {
+        self.hit && (!self.condition || (self.assert_type == AssertType::Reachability && !self.must_hit))
+    }
+
+    /// The id to group the entries of this assertion by. Some older emitters
+    /// leave `id` empty, their assertions are told apart by where they are
+    /// instead, as `file:function:begin_line`.
+    pub fn group_id(&self) -> Cow<'_, str> {
+        if !self.id.is_empty() {
+            return Cow::Borrowed(&self.id);
+        }
+        let location = &self.location;
+        let line = location.begin_line.map(|x| x.to_string()).unwrap_or_default();
+        Cow::Owned(format!("{}:{}:{}", location.file(), location.function(), line))
+    }
+}
+
+/// One `antithesis_guidance` line, written by the SDK next to the numeric
+/// assertions (e.g. `always_greater_than`) to steer the exploration.
+///
+/// Like assertions, entries with `hit == false` are catalog entries.
+#[derive(Deserialize, Clone, Debug)]
+pub struct AntithesisGuidance {
+    pub guidance_type: GuidanceType,
+    pub message: String,
+    pub id: String,
+    #[serde(default)]
+    pub location: Location,
+    /// Whether the exploration tries to make `left - right` larger rather than smaller.
+    #[serde(default)]
+    pub maximize: bool,
+    /// `{"left": .., "right": ..}` for numeric guidance.
+    pub guidance_data: Value,
+    #[serde(default)]
+    pub hit: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuidanceType {
+    Numeric,
+    Boolean,
+    Json,
+}
+
+/// A single parsed line of SDK output.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SDKInput {
+    AntithesisSdk(AntithesisSdk),
+    AntithesisAssert(AntithesisAssert),
+    AntithesisSetup(AntithesisSetup),
+    AntithesisGuidance(AntithesisGuidance),
+
+    /// Any other single-key object, as written by `send_event()`.
+    SendEvent{event_name: String, details: Value }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AssertType {
+    Always,
+    Sometimes,
+    Reachability,
+    /// A type this version doesn't know, e.g. from a newer SDK. Assertions
+    /// of an unknown type are reported without being evaluated.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl AssertType {
+    /// The name used for this type in the SDK output.
+    pub fn as_str(&self) -> &str {
+        match self {
+            AssertType::Always => "always",
+            AssertType::Sometimes => "sometimes",
+            AssertType::Reachability => "reachability",
+            AssertType::Unknown(x) => x,
+        }
+    }
+}
+
+impl FromStr for AssertType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(AssertType::Always),
+            "sometimes" => Ok(AssertType::Sometimes),
+            "reachability" => Ok(AssertType::Reachability),
+            _ => Err(format!("unknown assert type {} (expected always, sometimes or reachability)", s)),
+        }
+    }
+}
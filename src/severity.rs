@@ -0,0 +1,60 @@
+use anyhow::{ Context, Result };
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::evaluate::EvaluatedAssertion;
+
+/// How much a failed assertion matters. Only errors fail the run.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+/// The severity of the assertions of each `display_type`, anything not
+/// listed is an error.
+///
+/// Read from TOML such as:
+///
+/// ```toml
+/// Sometimes = "warning"
+/// Reachable = "info"
+/// ```
+#[derive(Deserialize, Default, Debug)]
+#[serde(transparent)]
+pub struct Severities {
+    by_display_type: BTreeMap<String, Severity>,
+}
+
+impl Severities {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("unable to parse {}", path.display()))
+    }
+
+    pub fn of(&self, display_type: &str) -> Severity {
+        self.by_display_type.get(display_type).copied().unwrap_or_default()
+    }
+
+    pub fn apply(&self, assertions: &mut [EvaluatedAssertion]) {
+        for evaled in assertions {
+            evaled.severity = self.of(&evaled.display_type);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// Timestamps that some pipelines add to each line of SDK output,
+// e.g. `{"ts": 1718000000.5, "antithesis_assert": {...}}`.
+
+use chrono::DateTime;
+use serde::de::{ Error };
+use serde::{ Deserialize, Deserializer, Serialize, Serializer };
+use serde_json::{ Value };
+
+/// When a line was written, as given by its timestamp field.
+///
+/// Numbers are taken as seconds since the epoch, strings as RFC 3339.
+#[derive(Clone, Debug)]
+pub struct Timestamp {
+    /// The field as it was written, which is what reports show.
+    pub value: Value,
+    /// Seconds since the epoch, for comparing and subtracting.
+    pub seconds: f64,
+}
+
+impl Timestamp {
+    /// None when `value` is neither a number nor an RFC 3339 string.
+    pub fn parse(value: Value) -> Option<Self> {
+        let seconds = match &value {
+            Value::Number(x) => x.as_f64()?,
+            Value::String(x) => {
+                let time = DateTime::parse_from_rfc3339(x).ok()?;
+                time.timestamp() as f64 + f64::from(time.timestamp_subsec_nanos()) / 1e9
+            },
+            _ => return None,
+        };
+        Some(Timestamp { value, seconds })
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Timestamp::parse(value).ok_or_else(|| D::Error::custom("expected seconds since the epoch or an RFC 3339 time"))
+    }
+}
+
+/// The earliest and latest of some timestamps.
+#[derive(Default, Clone, Debug)]
+pub struct Span {
+    pub first: Option<Timestamp>,
+    pub last: Option<Timestamp>,
+}
+
+impl Span {
+    pub fn record(&mut self, timestamp: &Timestamp) {
+        if self.first.as_ref().is_none_or(|x| timestamp.seconds < x.seconds) {
+            self.first = Some(timestamp.clone());
+        }
+        if self.last.as_ref().is_none_or(|x| timestamp.seconds > x.seconds) {
+            self.last = Some(timestamp.clone());
+        }
+    }
+
+    pub fn merge(&mut self, other: Span) {
+        for timestamp in other.first.iter().chain(other.last.iter()) {
+            self.record(timestamp);
+        }
+    }
+
+    /// Seconds from the first to the last, None when there are none.
+    pub fn duration(&self) -> Option<f64> {
+        Some(self.last.as_ref()?.seconds - self.first.as_ref()?.seconds)
+    }
+}
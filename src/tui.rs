@@ -0,0 +1,177 @@
+// Terminal browser for the assertions of a report: a list on the left that
+// can be searched, and the selected assertion in full on the right.
+
+use anyhow::Result;
+use ratatui::crossterm::event::{ self, Event, KeyCode, KeyEventKind, KeyModifiers };
+use ratatui::layout::{ Constraint, Layout };
+use ratatui::style::{ Color, Modifier, Style };
+use ratatui::text::{ Line, Span };
+use ratatui::widgets::{ Block, List, ListItem, ListState, Paragraph, Wrap };
+use ratatui::{ DefaultTerminal, Frame };
+
+use crunch::{ EvaluatedAssertion, Status };
+
+// How far PageUp/PageDown move the list
+const PAGE: usize = 10;
+
+struct Browser {
+    assertions: Vec<EvaluatedAssertion>,
+    // Indexes into assertions of those matching the search
+    shown: Vec<usize>,
+    list: ListState,
+    search: String,
+    searching: bool,
+    scroll: u16,
+}
+
+/// Browse `assertions` until the user quits with `q` or Esc.
+pub fn run(assertions: Vec<EvaluatedAssertion>) -> Result<()> {
+    let mut browser = Browser {
+        shown: (0..assertions.len()).collect(),
+        assertions,
+        list: ListState::default().with_selected(Some(0)),
+        search: String::new(),
+        searching: false,
+        scroll: 0,
+    };
+    ratatui::run(|terminal| browser.browse(terminal))
+}
+
+impl Browser {
+    fn browse(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.searching {
+                match key.code {
+                    KeyCode::Enter => self.searching = false,
+                    KeyCode::Esc => {
+                        self.searching = false;
+                        self.set_search(String::new());
+                    },
+                    KeyCode::Backspace => {
+                        let mut search = self.search.clone();
+                        search.pop();
+                        self.set_search(search);
+                    },
+                    KeyCode::Char(c) => self.set_search(format!("{}{}", self.search, c)),
+                    _ => {},
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Esc if self.search.is_empty() => return Ok(()),
+                KeyCode::Esc => self.set_search(String::new()),
+                KeyCode::Char('/') => self.searching = true,
+                KeyCode::Down | KeyCode::Char('j') => self.select_by(1),
+                KeyCode::Up | KeyCode::Char('k') => self.select_by(-1),
+                KeyCode::PageDown => self.select_by(PAGE as isize),
+                KeyCode::PageUp => self.select_by(-(PAGE as isize)),
+                KeyCode::Home | KeyCode::Char('g') => self.select(0),
+                KeyCode::End | KeyCode::Char('G') => self.select(self.shown.len().saturating_sub(1)),
+                KeyCode::Char('J') => self.scroll = self.scroll.saturating_add(1),
+                KeyCode::Char('K') => self.scroll = self.scroll.saturating_sub(1),
+                _ => {},
+            }
+        }
+    }
+
+    fn set_search(&mut self, search: String) {
+        self.search = search;
+        self.shown = (0..self.assertions.len())
+            .filter(|i| {
+                let evaled = &self.assertions[*i];
+                fuzzy_match(&self.search, &evaled.id) || fuzzy_match(&self.search, &evaled.message)
+            })
+            .collect();
+        self.select(0);
+    }
+
+    fn select_by(&mut self, delta: isize) {
+        let current = self.list.selected().unwrap_or(0);
+        self.select(current.saturating_add_signed(delta));
+    }
+
+    fn select(&mut self, i: usize) {
+        let i = i.min(self.shown.len().saturating_sub(1));
+        self.list.select((!self.shown.is_empty()).then_some(i));
+        self.scroll = 0;
+    }
+
+    fn selected(&self) -> Option<&EvaluatedAssertion> {
+        self.list.selected().and_then(|i| self.shown.get(i)).map(|i| &self.assertions[*i])
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+
+        let items: Vec<ListItem> = self.shown.iter().map(|i| {
+            let evaled = &self.assertions[*i];
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<12}", label(evaled)), Style::new().fg(color(evaled))),
+                Span::raw(evaled.id.clone()),
+            ]))
+        }).collect();
+        let title = format!(" {} of {} assertions ", self.shown.len(), self.assertions.len());
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let details = match self.selected() {
+            Some(evaled) => serde_json::to_string_pretty(evaled).unwrap_or_default(),
+            None => String::new(),
+        };
+        let paragraph = Paragraph::new(details)
+            .block(Block::bordered().title(" details "))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(paragraph, right);
+
+        let help = if self.searching {
+            format!("/{}", self.search)
+        } else if !self.search.is_empty() {
+            format!("search: {}  (Esc to clear)  j/k move  J/K scroll details  / search  q quit", self.search)
+        } else {
+            "j/k move  PgUp/PgDn page  J/K scroll details  / search  q quit".to_string()
+        };
+        frame.render_widget(Line::from(help).style(Style::new().add_modifier(Modifier::DIM)), footer);
+    }
+}
+
+fn label(evaled: &EvaluatedAssertion) -> &'static str {
+    if evaled.expected_failure && !evaled.passed {
+        return "xfail";
+    }
+    match evaled.status {
+        Status::Passed => "passed",
+        Status::Failed => "FAILED",
+        Status::NotHit => "not hit",
+        Status::NotReached => "not reached",
+        Status::Unevaluated => "unevaluated",
+    }
+}
+
+fn color(evaled: &EvaluatedAssertion) -> Color {
+    match evaled.status {
+        _ if evaled.expected_failure && !evaled.passed => Color::DarkGray,
+        Status::Passed => Color::Green,
+        Status::Failed => Color::Red,
+        Status::NotHit | Status::NotReached => Color::Yellow,
+        Status::Unevaluated => Color::DarkGray,
+    }
+}
+
+// Whether the characters of `pattern` appear in `text` in order, ignoring case
+fn fuzzy_match(pattern: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    pattern.chars().flat_map(char::to_lowercase).all(|c| text.any(|x| x == c))
+}